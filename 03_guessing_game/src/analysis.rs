@@ -0,0 +1,111 @@
+use std::fmt;
+
+use game::Game;
+
+// The number of attempts binary search needs in the worst case to find
+// any number in the `min..=max` range.
+//
+// Each guess in the middle of the interval halves it, so for `n`
+// numbers we need `floor(log2(n)) + 1` guesses, which is the number of
+// bits in `n`.
+pub fn optimal_attempts(min: u32, max: u32) -> u32 {
+    if min > max {
+        return 0;
+    }
+    let count = u64::from(max - min) + 1;
+    64 - count.leading_zeros()
+}
+
+// End-of-game comparison of the player's attempts with binary search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Analysis {
+    pub attempts: u32,
+    pub optimal: u32,
+    pub wasted_guesses: u32,
+}
+
+impl Analysis {
+    pub fn new(game: &Game) -> Analysis {
+        let config = game.config();
+        Analysis {
+            attempts: game.attempts(),
+            optimal: optimal_attempts(config.min, config.max),
+            wasted_guesses: game.wasted_guesses(),
+        }
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "You used {} attempts, binary search needs at most {}", self.attempts, self.optimal)?;
+        if self.attempts < self.optimal {
+            write!(f, " - lucky!")?;
+        } else if self.attempts == self.optimal {
+            write!(f, " - optimal play.")?;
+        } else {
+            write!(f, " - {} more than optimal.", self.attempts - self.optimal)?;
+        }
+        if self.wasted_guesses > 0 {
+            write!(f, "\n{} of your guesses were outside of the known interval.", self.wasted_guesses)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Config, Difficulty};
+
+    #[test]
+    fn optimal_attempts_for_small_ranges() {
+        // One number: the first guess is right.
+        assert_eq!(optimal_attempts(5, 5), 1);
+        // Two numbers: the middle is the lower one, then the upper one.
+        assert_eq!(optimal_attempts(5, 6), 2);
+        assert_eq!(optimal_attempts(1, 3), 2);
+        assert_eq!(optimal_attempts(6, 5), 0);
+    }
+
+    #[test]
+    fn optimal_attempts_for_powers_of_two() {
+        // `2^k - 1` numbers take `k` attempts, one more number takes one
+        // more attempt.
+        for k in 1..32 {
+            let count: u32 = 1 << k;
+            assert_eq!(optimal_attempts(1, count - 1), k, "{} numbers", count - 1);
+            assert_eq!(optimal_attempts(1, count), k + 1, "{} numbers", count);
+        }
+        assert_eq!(optimal_attempts(0, u32::MAX), 33);
+    }
+
+    #[test]
+    fn optimal_attempts_for_difficulties() {
+        assert_eq!(optimal_attempts(1, 10), 4);
+        assert_eq!(optimal_attempts(1, 100), 7);
+        assert_eq!(optimal_attempts(1, 1000), 10);
+        // The attempts limits of the presets are enough for binary search.
+        for config in &[Difficulty::Normal.config(), Difficulty::Hard.config()] {
+            assert!(optimal_attempts(config.min, config.max) <= config.max_attempts.unwrap());
+        }
+    }
+
+    #[test]
+    fn analysis_report() {
+        let mut game = Game::with_secret(Config::default(), 34);
+        game.guess(50);
+        game.guess(60);
+        game.guess(34);
+        let analysis = Analysis::new(&game);
+        assert_eq!(analysis, Analysis { attempts: 3, optimal: 7, wasted_guesses: 1 });
+        assert_eq!(
+            analysis.to_string(),
+            "You used 3 attempts, binary search needs at most 7 - lucky!\n\
+             1 of your guesses were outside of the known interval."
+        );
+        let analysis = Analysis { attempts: 7, optimal: 7, wasted_guesses: 0 };
+        assert_eq!(analysis.to_string(), "You used 7 attempts, binary search needs at most 7 - optimal play.");
+        let analysis = Analysis { attempts: 9, optimal: 7, wasted_guesses: 0 };
+        assert_eq!(analysis.to_string(), "You used 9 attempts, binary search needs at most 7 - 2 more than optimal.");
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use secret::{RandomSource, SecretSource};
+
+// Game settings: the range of the secret number (both ends are
+// included) and the maximum number of attempts (`None` means
+// unlimited).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    pub min: u32,
+    pub max: u32,
+    pub max_attempts: Option<u32>,
+}
+
+// Defaults match the original game: number from 1 to 100 and unlimited
+// attempts.
+impl Default for Config {
+    fn default() -> Config {
+        Config { min: 1, max: 100, max_attempts: None }
+    }
+}
+
+impl Config {
+    // Check that the range is not empty, so we can pick a secret
+    // number from it (the `max + 1` must also fit into u32, since the
+    // `gen_range` upper bound is exclusive).
+    pub fn is_valid(&self) -> bool {
+        self.min <= self.max && self.max < u32::MAX && self.max_attempts != Some(0)
+    }
+}
+
+// Difficulty presets, each one maps to the `Config`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn config(&self) -> Config {
+        match *self {
+            Difficulty::Easy => Config { min: 1, max: 10, max_attempts: None },
+            Difficulty::Normal => Config { min: 1, max: 100, max_attempts: Some(10) },
+            Difficulty::Hard => Config { min: 1, max: 1000, max_attempts: Some(10) },
+        }
+    }
+}
+
+// Implementing the `FromStr` trait allows to do `"easy".parse()`.
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("unknown difficulty: {} (expected easy, normal or hard)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The result of a single guess.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    TooSmall,
+    TooBig,
+    Win,
+    // Wrong guess and no attempts left.
+    Lose,
+}
+
+#[derive(Debug)]
+pub struct Game {
+    config: Config,
+    secret_number: u32,
+    attempts: u32,
+    finished: bool,
+    // The interval where the secret number can be, according to the
+    // answers given so far (both ends are included).
+    low: u32,
+    high: u32,
+    // Guesses outside of the known interval, they give no new
+    // information about the secret number.
+    wasted_guesses: u32,
+    guesses: Vec<u32>,
+}
+
+impl Game {
+    // Start the game with a random secret number.
+    pub fn new(config: Config) -> Game {
+        Game::with_source(config, &mut RandomSource)
+    }
+
+    // Start the game with the secret number taken from the given source.
+    pub fn with_source<S: SecretSource + ?Sized>(config: Config, source: &mut S) -> Game {
+        let secret_number = source.secret_number(config.min, config.max);
+        Game::with_secret(config, secret_number)
+    }
+
+    // Start the game with a known secret number.
+    pub fn with_secret(config: Config, secret_number: u32) -> Game {
+        Game {
+            config,
+            secret_number,
+            attempts: 0,
+            finished: false,
+            low: config.min,
+            high: config.max,
+            wasted_guesses: 0,
+            guesses: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn secret_number(&self) -> u32 {
+        self.secret_number
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    // Number of remaining attempts, `None` if attempts are unlimited.
+    pub fn attempts_left(&self) -> Option<u32> {
+        self.config.max_attempts.map(|max| max.saturating_sub(self.attempts))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // The narrowed interval `(low, high)` where the secret number is.
+    pub fn interval(&self) -> (u32, u32) {
+        (self.low, self.high)
+    }
+
+    pub fn wasted_guesses(&self) -> u32 {
+        self.wasted_guesses
+    }
+
+    // All guesses made so far, in order.
+    pub fn guesses(&self) -> &[u32] {
+        &self.guesses
+    }
+
+    pub fn has_guessed(&self, guess: u32) -> bool {
+        self.guesses.contains(&guess)
+    }
+
+    pub fn guess(&mut self, guess: u32) -> Outcome {
+        self.attempts += 1;
+        self.guesses.push(guess);
+        if guess < self.low || guess > self.high {
+            self.wasted_guesses += 1;
+        }
+
+        // The match construct is similar to the switch operator in C.
+        //
+        // `guess.cmp` returns the Order enum, we could do
+        //     Order result = guess.cmp(&secret_number)
+        //     match result { ... }
+        //
+        // So here we match the Enum instance against a set of patterns:
+        let outcome = match guess.cmp(&self.secret_number) {
+            Ordering::Less => {
+                self.low = self.low.max(guess + 1);
+                Outcome::TooSmall
+            }
+            Ordering::Greater => {
+                self.high = self.high.min(guess - 1);
+                Outcome::TooBig
+            }
+            Ordering::Equal => {
+                self.low = guess;
+                self.high = guess;
+                Outcome::Win
+            }
+        };
+
+        if outcome == Outcome::Win {
+            self.finished = true;
+            return outcome;
+        }
+        if self.attempts_left() == Some(0) {
+            self.finished = true;
+            return Outcome::Lose;
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_attempts: Option<u32>) -> Config {
+        Config { min: 1, max: 100, max_attempts }
+    }
+
+    #[test]
+    fn guess_outcomes() {
+        let mut game = Game::with_secret(config(None), 34);
+        assert_eq!(game.guess(20), Outcome::TooSmall);
+        assert_eq!(game.guess(50), Outcome::TooBig);
+        assert!(!game.is_finished());
+        assert_eq!(game.interval(), (21, 49));
+        assert_eq!(game.guess(34), Outcome::Win);
+        assert!(game.is_finished());
+        assert_eq!(game.attempts(), 3);
+        assert_eq!(game.guesses(), [20, 50, 34]);
+        assert_eq!(game.attempts_left(), None);
+    }
+
+    #[test]
+    fn lose_after_last_attempt() {
+        let mut game = Game::with_secret(config(Some(2)), 34);
+        assert_eq!(game.guess(1), Outcome::TooSmall);
+        assert_eq!(game.attempts_left(), Some(1));
+        assert_eq!(game.guess(2), Outcome::Lose);
+        assert!(game.is_finished());
+        assert_eq!(game.attempts_left(), Some(0));
+
+        // The last attempt can still win.
+        let mut game = Game::with_secret(config(Some(1)), 34);
+        assert_eq!(game.guess(34), Outcome::Win);
+    }
+
+    #[test]
+    fn guesses_at_range_ends() {
+        let mut game = Game::with_secret(Config { min: 0, max: 10, max_attempts: None }, 5);
+        // `guess - 1` and `guess + 1` must not overflow.
+        assert_eq!(game.guess(0), Outcome::TooSmall);
+        assert_eq!(game.guess(10), Outcome::TooBig);
+        assert_eq!(game.interval(), (1, 9));
+    }
+
+    #[test]
+    fn counts_wasted_guesses() {
+        let mut game = Game::with_secret(config(None), 34);
+        game.guess(50);
+        game.guess(60);
+        game.guess(20);
+        game.guess(10);
+        assert_eq!(game.wasted_guesses(), 2);
+    }
+
+    #[test]
+    fn config_validation() {
+        assert!(Config::default().is_valid());
+        assert!(Config { min: 5, max: 5, max_attempts: None }.is_valid());
+        assert!(!Config { min: 6, max: 5, max_attempts: None }.is_valid());
+        assert!(!Config { min: 1, max: u32::MAX, max_attempts: None }.is_valid());
+        assert!(!Config { min: 1, max: 10, max_attempts: Some(0) }.is_valid());
+        assert_eq!("HARD".parse(), Ok(Difficulty::Hard));
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use game::Game;
+
+// Everything that can go wrong when reading the player's guess.
+#[derive(Debug)]
+pub enum GuessError {
+    // The input is not a number, holds the (trimmed) input.
+    NotANumber(String),
+    OutOfRange { guess: u32, min: u32, max: u32 },
+    // The number was already tried in this game.
+    Duplicate(u32),
+    // The input is closed (Ctrl-D or the end of piped input).
+    Eof,
+    Io(io::Error),
+}
+
+impl GuessError {
+    // EOF and IO errors mean we can not read more input, the game can
+    // not continue.
+    // Other errors are just wrong input, the player can try again.
+    pub fn is_fatal(&self) -> bool {
+        matches!(*self, GuessError::Eof | GuessError::Io(_))
+    }
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GuessError::NotANumber(ref input) => write!(f, "'{}' is not a number, please input a number.", input),
+            GuessError::OutOfRange { guess, min, max } => {
+                write!(f, "{} is out of range, the number is between {} and {}.", guess, min, max)
+            }
+            GuessError::Duplicate(guess) => write!(f, "You already tried {}, try another number.", guess),
+            GuessError::Eof => write!(f, "No more input."),
+            GuessError::Io(ref err) => write!(f, "Failed to read input: {}", err),
+        }
+    }
+}
+
+impl error::Error for GuessError {}
+
+// Implementing the `From` trait allows to convert `io::Error` into
+// `GuessError` with the `?` operator.
+impl From<io::Error> for GuessError {
+    fn from(err: io::Error) -> GuessError {
+        GuessError::Io(err)
+    }
+}
+
+// Parse and validate the guess for the game.
+pub fn parse_guess(input: &str, game: &Game) -> Result<u32, GuessError> {
+    let input = input.trim();
+    // The `guess: u32` annotates the variable type, because `parse` can
+    // return different types and we need to specify the expected one.
+    let guess: u32 = input.parse().map_err(|_| GuessError::NotANumber(input.to_string()))?;
+
+    let config = game.config();
+    if guess < config.min || guess > config.max {
+        return Err(GuessError::OutOfRange { guess, min: config.min, max: config.max });
+    }
+    if game.has_guessed(guess) {
+        return Err(GuessError::Duplicate(guess));
+    }
+    Ok(guess)
+}
+
+// Read one line from the input and parse it as a guess.
+// Any `BufRead` implementation can be used: the locked stdin, a file or
+// an in-memory buffer.
+pub fn read_guess<R: BufRead + ?Sized>(input: &mut R, game: &Game) -> Result<u32, GuessError> {
+    // String::new() - here new() is "associated" function.
+    let mut line = String::new();
+
+    // the `&mut line` - here we pass line by reference.
+    // read_line returns io::Result object with the number of bytes
+    // read, the `?` returns the error (converted to GuessError) if
+    // reading failed. Zero bytes means that the input is closed.
+    if input.read_line(&mut line)? == 0 {
+        return Err(GuessError::Eof);
+    }
+    parse_guess(&line, game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Config;
+
+    fn game() -> Game {
+        Game::with_secret(Config { min: 1, max: 100, max_attempts: None }, 34)
+    }
+
+    #[test]
+    fn parses_guess() {
+        let mut game = game();
+        assert_eq!(parse_guess(" 42\n", &game).unwrap(), 42);
+        match parse_guess("abc\n", &game) {
+            Err(GuessError::NotANumber(ref input)) => assert_eq!(input, "abc"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(parse_guess("-1", &game), Err(GuessError::NotANumber(_))));
+        assert!(matches!(parse_guess("0", &game), Err(GuessError::OutOfRange { guess: 0, min: 1, max: 100 })));
+        assert!(matches!(parse_guess("101", &game), Err(GuessError::OutOfRange { guess: 101, .. })));
+        game.guess(42);
+        assert!(matches!(parse_guess("42", &game), Err(GuessError::Duplicate(42))));
+    }
+
+    #[test]
+    fn reads_until_end_of_input() {
+        let game = game();
+        let mut input: &[u8] = b"50\nabc\n";
+        assert_eq!(read_guess(&mut input, &game).unwrap(), 50);
+        let err = read_guess(&mut input, &game).unwrap_err();
+        assert!(!err.is_fatal());
+        let err = read_guess(&mut input, &game).unwrap_err();
+        assert!(matches!(err, GuessError::Eof));
+        assert!(err.is_fatal());
+        assert_eq!(err.to_string(), "No more input.");
+    }
+}
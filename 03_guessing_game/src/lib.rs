@@ -0,0 +1,31 @@
+// The game logic lives in the library part of the crate, so it can be
+// reused outside of `main` (the binary in `src/main.rs` only drives it).
+//
+// The `extern crate` and `pub mod` declarations make the items from
+// `src/game.rs` available as `guessing_game::game::Game`, and
+// `pub use` re-exports them so they can be imported directly from the
+// crate root: `use guessing_game::Game`.
+extern crate rand;
+extern crate serde;
+// `#[macro_use]` makes the `Serialize` and `Deserialize` derive macros
+// available in the crate.
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+pub mod analysis;
+pub mod game;
+pub mod input;
+pub mod net;
+pub mod reverse;
+pub mod scores;
+pub mod secret;
+pub mod solver;
+
+pub use analysis::Analysis;
+pub use game::{Config, Difficulty, Game, Outcome};
+pub use input::GuessError;
+pub use reverse::ReverseGame;
+pub use scores::{Score, ScoreBoard};
+pub use secret::{RandomSource, SecretSource, SeededSource};
+pub use solver::{BinarySearch, LinearScan, RandomGuess, Stats, Strategy};
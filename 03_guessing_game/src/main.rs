@@ -1,66 +1,345 @@
 // Note: cargo doc --open will generate and
 // open the documentation for the project dependencies.
-extern crate rand;
+extern crate guessing_game;
 
-use std::io;
+use std::env;
 use std::cmp::Ordering;
-use rand::Rng;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+
+use guessing_game::input;
+use guessing_game::net::{self, Mode};
+use guessing_game::reverse;
+use guessing_game::scores;
+use guessing_game::{
+    Analysis, BinarySearch, Config, Difficulty, Game, LinearScan, Outcome, RandomGuess, RandomSource, ReverseGame,
+    Score, ScoreBoard, SecretSource, SeededSource, Strategy,
+};
+use guessing_game::solver;
+
+// Default location of the high-score table.
+const SCORES_FILE: &str = "scores.json";
+
+// Command line options.
+struct Options {
+    config: Config,
+    // Seed for the secret number generator, makes the game reproducible.
+    seed: Option<u64>,
+    // Player name for the high-score table.
+    name: String,
+    scores_file: PathBuf,
+    // Only show the high-score table and exit.
+    show_scores: bool,
+    // Show the interval where the number is after each guess.
+    hints: bool,
+    // Number of games for the computer players to simulate.
+    auto_games: Option<u32>,
+    // Computer player strategy, all strategies are used if not set.
+    strategy: Option<String>,
+    // Address to run the multiplayer server on.
+    serve: Option<String>,
+    // Address of the multiplayer server to connect to.
+    connect: Option<String>,
+    mode: Mode,
+    // The player thinks of a number and the computer guesses it.
+    reverse: bool,
+}
+
+// Usage:
+//
+//     cargo run -- [--difficulty easy|normal|hard] [--min N] [--max N] [--attempts N]
+//                  [--seed N] [--name NAME] [--scores-file PATH] [--hints]
+//     cargo run -- --scores [--scores-file PATH]
+//     cargo run -- --auto N [--strategy binary|random|linear] [--seed N] [...game options]
+//     cargo run -- --serve ADDR [--mode race|turns] [--seed N] [...game options]
+//     cargo run -- --connect ADDR [--name NAME]
+//     cargo run -- --reverse [--min N] [--max N]
+//
+// The `--min`, `--max` and `--attempts` options override the values
+// from the difficulty preset (by default the number is from 1 to 100
+// and attempts are unlimited).
+// The `--scores` shows the high-score table.
+// The `--hints` enables hints: after each guess the game shows the
+// interval where the secret number is.
+// The `--auto` makes the computer play N games and shows statistics for
+// each strategy.
+// The `--serve` runs the multiplayer server (for example, on
+// `127.0.0.1:4000`) and `--connect` connects to it.
+// The `--reverse` switches roles: the computer guesses the player's
+// number.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut config = Config::default();
+    let mut seed = None;
+    // The player name defaults to the system user name.
+    let mut name = env::var("USER").unwrap_or_else(|_| String::from("anonymous"));
+    let mut scores_file = PathBuf::from(SCORES_FILE);
+    let mut show_scores = false;
+    let mut hints = false;
+    let mut auto_games = None;
+    let mut strategy = None;
+    let mut serve = None;
+    let mut connect = None;
+    let mut mode = Mode::Race;
+    let mut reverse = false;
+    // `iter()` returns an iterator, we call `next()` to get the option
+    // value that follows the option name.
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--difficulty" => {
+                let difficulty: Difficulty = value()?.parse()?;
+                config = difficulty.config();
+            }
+            "--min" => config.min = parse_number(value()?)?,
+            "--max" => config.max = parse_number(value()?)?,
+            "--attempts" => config.max_attempts = Some(parse_number(value()?)?),
+            "--seed" => seed = Some(parse_number(value()?)?),
+            "--name" => {
+                name = value()?.trim().to_string();
+                if name.is_empty() {
+                    return Err(String::from("--name should not be empty"));
+                }
+            }
+            "--scores-file" => scores_file = PathBuf::from(value()?),
+            "--scores" => show_scores = true,
+            "--hints" => hints = true,
+            "--auto" => auto_games = Some(parse_number(value()?)?),
+            "--strategy" => strategy = Some(value()?.clone()),
+            "--serve" => serve = Some(value()?.clone()),
+            "--connect" => connect = Some(value()?.clone()),
+            "--mode" => mode = value()?.parse()?,
+            "--reverse" => reverse = true,
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    if !config.is_valid() {
+        return Err(format!("invalid game settings: {:?}", config));
+    }
+    Ok(Options { config, seed, name, scores_file, show_scores, hints, auto_games, strategy, serve, connect, mode, reverse })
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("not a number: {}", value))
+}
+
+// The `Box<dyn SecretSource>` is a "trait object", it can hold any
+// type that implements the `SecretSource` trait, so we can choose
+// the implementation at runtime.
+// The `Send` means the source can be moved to another thread (this is
+// needed for the multiplayer server).
+fn secret_source(seed: Option<u64>) -> Box<dyn SecretSource + Send> {
+    match seed {
+        Some(seed) => Box::new(SeededSource::new(seed)),
+        None => Box::new(RandomSource),
+    }
+}
+
+// All computer player strategies.
+fn strategies(seed: Option<u64>) -> Vec<Box<dyn Strategy>> {
+    let random: Box<dyn Strategy> = match seed {
+        // Use a different seed than for the secret numbers, otherwise
+        // the random player would always guess right from the first try.
+        Some(seed) => Box::new(RandomGuess::new(SeededSource::new(seed.wrapping_add(1)))),
+        None => Box::new(RandomGuess::new(RandomSource)),
+    };
+    vec![Box::new(BinarySearch), random, Box::new(LinearScan)]
+}
+
+// Simulate games played by the computer and print statistics.
+fn run_auto(options: &Options, games: u32) -> Result<(), String> {
+    let mut strategies = strategies(options.seed);
+    if let Some(ref name) = options.strategy {
+        strategies.retain(|strategy| strategy.name() == name.as_str());
+        if strategies.is_empty() {
+            return Err(format!("unknown strategy: {} (expected binary, random or linear)", name));
+        }
+    }
+    let config = options.config;
+    println!("Simulating {} games, numbers from {} to {}.", games, config.min, config.max);
+    for strategy in &mut strategies {
+        // Each strategy gets the same sequence of secret numbers (when
+        // the seed is set), so the results are comparable.
+        let mut source = secret_source(options.seed);
+        let stats = solver::simulate(config, &mut *source, &mut **strategy, games);
+        println!();
+        println!("Strategy: {}", strategy.name());
+        print!("{}", stats);
+    }
+    Ok(())
+}
+
+// The computer guesses the number, the player answers.
+fn run_reverse(config: Config) {
+    println!("Think of a number between {} and {}, I will guess it.", config.min, config.max);
+    println!("Answer with higher (h), lower (l) or correct (c).");
+
+    let mut game = ReverseGame::new(config, BinarySearch);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        let guess = game.next_guess();
+        println!("Is it {}?", guess);
+
+        // Read lines until we get a valid answer, stop if the input
+        // is closed.
+        let answer = loop {
+            match lines.next() {
+                Some(Ok(line)) => match reverse::parse_answer(&line) {
+                    Some(answer) => break answer,
+                    None => println!("Please answer higher (h), lower (l) or correct (c)."),
+                },
+                Some(Err(err)) => {
+                    println!("Failed to read input: {}", err);
+                    return;
+                }
+                None => {
+                    println!("No more input.");
+                    return;
+                }
+            }
+        };
+
+        match game.answer(answer) {
+            Ok(Some(number)) => {
+                println!("Your number is {}, found in {} attempts!", number, game.attempts());
+                return;
+            }
+            Ok(None) => {
+                let hint = if answer == Ordering::Less { "lower" } else { "higher" };
+                println!("OK, {} than {}.", hint, guess);
+            }
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        }
+    }
+}
 
 fn main() {
+    // Skip the first argument, it is the program name.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+
+    let config = options.config;
+
+    if let Some(ref addr) = options.serve {
+        if let Err(err) = net::serve(addr.as_str(), options.mode, config, secret_source(options.seed)) {
+            eprintln!("Server error: {}", err);
+            process::exit(1);
+        }
+        return;
+    }
+    if let Some(ref addr) = options.connect {
+        if let Err(err) = net::connect(addr.as_str(), &options.name) {
+            eprintln!("Connection error: {}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if options.reverse {
+        run_reverse(config);
+        return;
+    }
+
+    if let Some(games) = options.auto_games {
+        if let Err(err) = run_auto(&options, games) {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+        return;
+    }
+
+    let mut board = match ScoreBoard::load(&options.scores_file) {
+        Ok(board) => board,
+        Err(err) => {
+            eprintln!("Failed to load scores from {}: {}", options.scores_file.display(), err);
+            process::exit(1);
+        }
+    };
+    if options.show_scores {
+        print!("{}", scores::format_table(board.scores()));
+        return;
+    }
+
     println!("Guess the number!");
+    println!("The number is between {} and {}.", config.min, config.max);
+    if let Some(max_attempts) = config.max_attempts {
+        println!("You have {} attempts.", max_attempts);
+    }
+
+    let top = board.top(config.min, config.max, 5);
+    if !top.is_empty() {
+        println!("High scores:");
+        print!("{}", scores::format_table(top));
+    }
+
+    let mut source = secret_source(options.seed);
 
     // We don't specify the variable type explicitly,
     // but the language is strongly typed, the type is derived
-    // from the method's return type.
-    let secret_number = rand::thread_rng().gen_range(1, 101);
-    println!("The number: {}", secret_number);
+    // from the function's return type.
+    let mut game = Game::with_source(config, &mut *source);
+    let started = Instant::now();
 
     println!("Please input your guess.");
 
-    loop {
-        // By default variables are immutable, for example:
-        // let x = 5;
-        // `let mut guess` declares mutable variable.
-        //
-        // String::new() - here new() is "associated" function.
-        let mut guess = String::new();
-
-        // the `&mut guess` - here we pass guess by reference.
-        io::stdin().read_line(&mut guess)
-            // read_line returns io::Result object
-            // io::Result is an enum with Ok and Err variants (enum values)
-            // when we call Err.expect(...), the app will crash with specified
-            // message if he Err result is returned.
-            // when we call Ok.expect(...), it returns the value in Ok (number
-            // of bytes read in this case).
-            .expect("Failed to read line.");
-
-        // We already have "guess", but we can "shadow" (redefine) the
-        // existing variable.
-        // The `guess: u32` annotates the variable type, because `parse` can
-        // return different types and we need to specify the expected one.
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            // `continue` goes back to the start of the loop
-            Err(_) => continue
+    // Lock the stdin once, the locked handle implements `BufRead`.
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    while !game.is_finished() {
+        let guess = match input::read_guess(&mut input, &game) {
+            Ok(guess) => guess,
+            // We can not read more input, stop the game.
+            Err(ref err) if err.is_fatal() => {
+                println!("{}", err);
+                println!("Bye! The number was {}.", game.secret_number());
+                return;
+            }
+            // Wrong input, let the player try again.
+            // `continue` goes back to the start of the loop.
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
         };
 
         println!("You guessed: {}", guess);
 
-        // The match construct is similar to the switch operator in C.
-        //
-        // `guess.cmp` returns the Order enum, we could do
-        //     Order result = guess.cmp(&secret_number)
-        //     match result { ... }
-        //
-        // So here we match the Enum instance against a set of patterns:
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small!"),
-            Ordering::Greater => println!("Too big!"),
-            Ordering::Equal => {
+        match game.guess(guess) {
+            Outcome::TooSmall => println!("Too small!"),
+            Outcome::TooBig => println!("Too big!"),
+            Outcome::Win => {
                 println!("You win!");
-                break;
+                let score = Score::new(
+                    &options.name, config.min, config.max, game.attempts(), started.elapsed());
+                board.add(score);
+                if let Err(err) = board.save(&options.scores_file) {
+                    eprintln!("Failed to save scores to {}: {}", options.scores_file.display(), err);
+                }
             }
+            Outcome::Lose => {
+                println!("You lose! The number was {}.", game.secret_number());
+            }
+        }
+
+        if options.hints && !game.is_finished() {
+            let (low, high) = game.interval();
+            println!("Hint: the number is between {} and {}.", low, high);
         }
     }
+
+    println!("{}", Analysis::new(&game));
 }
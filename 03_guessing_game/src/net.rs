@@ -0,0 +1,720 @@
+// Multiplayer game over TCP.
+//
+// The server keeps one game, several clients connect and guess the same
+// secret number. In the "race" mode everyone can guess at any time and
+// the first correct guess wins, in the "turns" mode players guess one
+// after another. When the round is over, the server picks a new number.
+//
+// The protocol is line-based, so the server can also be used with
+// `nc` or `telnet`. Client messages:
+//
+//     NAME <name>      set the player name
+//     GUESS <number>   guess the number (a bare number also works)
+//     QUIT             leave the game
+//
+// Lines longer than `MAX_LINE` bytes are not accepted, the client that
+// sends one is disconnected.
+//
+// Server messages:
+//
+//     WELCOME <id> <min> <max> <mode>
+//     JOINED <id> <name>
+//     NAME <id> <name>
+//     LEFT <id> <name>
+//     ROUND <round>
+//     TURN <id> <name>
+//     GUESSED <id> <name> <guess> <too_small|too_big>
+//     WINNER <id> <name> <guess> <attempts>
+//     LOST <secret>
+//     ERROR <text>
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use game::{Config, Game, Outcome};
+use input;
+use secret::SecretSource;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    // Everyone guesses at any time, the first correct guess wins.
+    Race,
+    // Players guess one after another.
+    Turns,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "race" => Ok(Mode::Race),
+            "turns" => Ok(Mode::Turns),
+            _ => Err(format!("unknown mode: {} (expected race or turns)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mode::Race => write!(f, "race"),
+            Mode::Turns => write!(f, "turns"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Name(String),
+    // The guess is sent as is, the server validates it the same way as
+    // the input in the single player game.
+    Guess(String),
+    Quit,
+}
+
+impl FromStr for ClientMessage {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<ClientMessage, String> {
+        let line = line.trim();
+        // `splitn(2, ' ')` splits the line into the command and the rest.
+        let mut parts = line.splitn(2, ' ');
+        let command = parts.next().unwrap_or("");
+        let rest = parts.next().unwrap_or("").trim();
+        match command {
+            "" => Err(String::from("empty message")),
+            "NAME" if rest.is_empty() => Err(String::from("empty name")),
+            "NAME" => Ok(ClientMessage::Name(rest.to_string())),
+            "GUESS" => Ok(ClientMessage::Guess(rest.to_string())),
+            "QUIT" => Ok(ClientMessage::Quit),
+            // Treat anything else as a guess, so the server can be used
+            // with `nc`.
+            _ => Ok(ClientMessage::Guess(line.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClientMessage::Name(ref name) => write!(f, "NAME {}", name),
+            ClientMessage::Guess(ref guess) => write!(f, "GUESS {}", guess),
+            ClientMessage::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    Welcome { id: usize, min: u32, max: u32, mode: Mode },
+    Joined { id: usize, name: String },
+    Name { id: usize, name: String },
+    Left { id: usize, name: String },
+    Round(u32),
+    Turn { id: usize, name: String },
+    Guessed { id: usize, name: String, guess: u32, outcome: Outcome },
+    Winner { id: usize, name: String, guess: u32, attempts: u32 },
+    Lost { secret: u32 },
+    Error(String),
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ServerMessage::Welcome { id, min, max, mode } => write!(f, "WELCOME {} {} {} {}", id, min, max, mode),
+            ServerMessage::Joined { id, ref name } => write!(f, "JOINED {} {}", id, name),
+            ServerMessage::Name { id, ref name } => write!(f, "NAME {} {}", id, name),
+            ServerMessage::Left { id, ref name } => write!(f, "LEFT {} {}", id, name),
+            ServerMessage::Round(round) => write!(f, "ROUND {}", round),
+            ServerMessage::Turn { id, ref name } => write!(f, "TURN {} {}", id, name),
+            ServerMessage::Guessed { id, ref name, guess, outcome } => {
+                let outcome = if outcome == Outcome::TooSmall { "too_small" } else { "too_big" };
+                write!(f, "GUESSED {} {} {} {}", id, name, guess, outcome)
+            }
+            ServerMessage::Winner { id, ref name, guess, attempts } => {
+                write!(f, "WINNER {} {} {} {}", id, name, guess, attempts)
+            }
+            ServerMessage::Lost { secret } => write!(f, "LOST {}", secret),
+            ServerMessage::Error(ref text) => write!(f, "ERROR {}", text),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<ServerMessage, String> {
+        let invalid = || format!("invalid message: {}", line);
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // Get the part at the given index, `?` returns the error if the
+        // part is missing or can not be parsed.
+        let arg = |i: usize| parts.get(i).cloned().ok_or_else(invalid);
+        let num = |i: usize| arg(i)?.parse::<u32>().map_err(|_| invalid());
+        let id = |i: usize| arg(i)?.parse::<usize>().map_err(|_| invalid());
+        let name = |i: usize| arg(i).map(String::from);
+
+        let message = match arg(0)? {
+            "WELCOME" => ServerMessage::Welcome { id: id(1)?, min: num(2)?, max: num(3)?, mode: arg(4)?.parse()? },
+            "JOINED" => ServerMessage::Joined { id: id(1)?, name: name(2)? },
+            "NAME" => ServerMessage::Name { id: id(1)?, name: name(2)? },
+            "LEFT" => ServerMessage::Left { id: id(1)?, name: name(2)? },
+            "ROUND" => ServerMessage::Round(num(1)?),
+            "TURN" => ServerMessage::Turn { id: id(1)?, name: name(2)? },
+            "GUESSED" => {
+                let outcome = match arg(4)? {
+                    "too_small" => Outcome::TooSmall,
+                    "too_big" => Outcome::TooBig,
+                    _ => return Err(invalid()),
+                };
+                ServerMessage::Guessed { id: id(1)?, name: name(2)?, guess: num(3)?, outcome }
+            }
+            "WINNER" => ServerMessage::Winner { id: id(1)?, name: name(2)?, guess: num(3)?, attempts: num(4)? },
+            "LOST" => ServerMessage::Lost { secret: num(1)? },
+            "ERROR" => ServerMessage::Error(line.trim()["ERROR".len()..].trim().to_string()),
+            _ => return Err(invalid()),
+        };
+        Ok(message)
+    }
+}
+
+// Human-readable text for the server message, `my_id` is the id of the
+// player who reads the message.
+pub fn describe(message: &ServerMessage, my_id: Option<usize>) -> String {
+    let who = |id: usize, name: &str| {
+        if Some(id) == my_id { String::from("You") } else { name.to_string() }
+    };
+    match *message {
+        ServerMessage::Welcome { id, min, max, mode } => format!(
+            "Connected as player {}, guess the number between {} and {} ({} mode).", id, min, max, mode),
+        ServerMessage::Joined { ref name, .. } => format!("{} joined the game.", name),
+        ServerMessage::Name { id, ref name } => format!("Player {} is now known as {}.", id, name),
+        ServerMessage::Left { ref name, .. } => format!("{} left the game.", name),
+        ServerMessage::Round(round) => format!("Round {}: a new number is picked.", round),
+        ServerMessage::Turn { id, ref name } => {
+            if Some(id) == my_id { String::from("Your turn!") } else { format!("{}'s turn.", name) }
+        }
+        ServerMessage::Guessed { id, ref name, guess, outcome } => {
+            let answer = if outcome == Outcome::TooSmall { "too small" } else { "too big" };
+            format!("{} guessed {}: {}!", who(id, name), guess, answer)
+        }
+        ServerMessage::Winner { id, ref name, guess, attempts } => {
+            format!("{} guessed {} and won after {} attempts!", who(id, name), guess, attempts)
+        }
+        ServerMessage::Lost { secret } => format!("Nobody won, the number was {}.", secret),
+        ServerMessage::Error(ref text) => format!("Error: {}", text),
+    }
+}
+
+// How many messages can wait to be written to one client, a client that
+// doesn't read them is disconnected.
+const QUEUE_SIZE: usize = 256;
+
+// The longest line a client can send (without the line end).
+pub const MAX_LINE: usize = 256;
+
+// Read the line from the client, `Ok(None)` at the end of the input.
+// `lines()` would keep reading a line without the line end forever, so
+// we read at most one byte more than `MAX_LINE` and fail if the line
+// is still not complete.
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let limit = MAX_LINE as u64 + 2;
+    if reader.take(limit).read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    if line.len() > MAX_LINE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(Some(line))
+}
+
+struct Player {
+    id: usize,
+    name: String,
+    // Used to disconnect the player, the messages are written by the
+    // player's writer thread.
+    stream: TcpStream,
+    queue: SyncSender<String>,
+    attempts: u32,
+}
+
+impl Player {
+    // Queue the message for the writer thread, so the server (and the
+    // other clients waiting for the lock) never waits for a slow client.
+    // If the queue is full, the client is disconnected, its thread will
+    // notice that and remove the player.
+    fn send(&self, message: &ServerMessage) {
+        if self.queue.try_send(message.to_string()).is_err() {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+// Write the queued messages to the client, stops when the player is
+// removed (the queue is closed) or the client is gone.
+fn write_messages(mut stream: TcpStream, queue: Receiver<String>) {
+    for line in queue {
+        if writeln!(stream, "{}", line).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            break;
+        }
+    }
+}
+
+// The server state, shared between the client threads.
+struct Server {
+    mode: Mode,
+    config: Config,
+    source: Box<dyn SecretSource + Send>,
+    game: Game,
+    round: u32,
+    players: Vec<Player>,
+    // Index of the player whose turn it is (in the "turns" mode).
+    turn: usize,
+    next_id: usize,
+}
+
+impl Server {
+    fn new(mode: Mode, config: Config, mut source: Box<dyn SecretSource + Send>) -> Server {
+        let game = Game::with_source(config, &mut *source);
+        Server { mode, config, source, game, round: 1, players: Vec::new(), turn: 0, next_id: 1 }
+    }
+
+    fn send(&self, id: usize, message: &ServerMessage) {
+        if let Some(player) = self.players.iter().find(|p| p.id == id) {
+            player.send(message);
+        }
+    }
+
+    fn broadcast(&self, message: &ServerMessage) {
+        for player in &self.players {
+            player.send(message);
+        }
+    }
+
+    fn current_player(&self) -> Option<&Player> {
+        self.players.get(self.turn)
+    }
+
+    // Whose turn it is, `None` in the "race" mode.
+    fn turn_message(&self) -> Option<ServerMessage> {
+        if self.mode != Mode::Turns {
+            return None;
+        }
+        self.current_player().map(|player| ServerMessage::Turn { id: player.id, name: player.name.clone() })
+    }
+
+    fn announce_turn(&self) {
+        if let Some(message) = self.turn_message() {
+            self.broadcast(&message);
+        }
+    }
+
+    fn join(&mut self, stream: TcpStream, queue: SyncSender<String>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = format!("player{}", id);
+        self.players.push(Player { id, name: name.clone(), stream, queue, attempts: 0 });
+
+        let (min, max) = (self.config.min, self.config.max);
+        self.send(id, &ServerMessage::Welcome { id, min, max, mode: self.mode });
+        self.broadcast(&ServerMessage::Joined { id, name });
+        // Only the new player needs to know whose turn it is.
+        if let Some(message) = self.turn_message() {
+            self.send(id, &message);
+        }
+        id
+    }
+
+    fn leave(&mut self, id: usize) {
+        let index = match self.players.iter().position(|p| p.id == id) {
+            Some(index) => index,
+            None => return,
+        };
+        let player = self.players.remove(index);
+        let _ = player.stream.shutdown(Shutdown::Both);
+        self.broadcast(&ServerMessage::Left { id, name: player.name });
+
+        // Keep the turn on the same player, or pass it to the next one
+        // if it was the turn of the player who left.
+        let was_current = index == self.turn;
+        if index < self.turn {
+            self.turn -= 1;
+        }
+        if self.turn >= self.players.len() {
+            self.turn = 0;
+        }
+        if was_current {
+            self.announce_turn();
+        }
+    }
+
+    fn new_round(&mut self) {
+        self.round += 1;
+        self.game = Game::with_source(self.config, &mut *self.source);
+        for player in &mut self.players {
+            player.attempts = 0;
+        }
+        let round = self.round;
+        self.broadcast(&ServerMessage::Round(round));
+        self.announce_turn();
+    }
+
+    // Handle the message from the player, returns `false` if the player
+    // wants to leave.
+    fn handle(&mut self, id: usize, message: ClientMessage) -> bool {
+        match message {
+            ClientMessage::Quit => return false,
+            ClientMessage::Name(name) => {
+                // Names are sent as a single word in server messages.
+                let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+                if let Some(player) = self.players.iter_mut().find(|p| p.id == id) {
+                    player.name = name.clone();
+                }
+                self.broadcast(&ServerMessage::Name { id, name });
+            }
+            ClientMessage::Guess(guess) => self.handle_guess(id, &guess),
+        }
+        true
+    }
+
+    fn handle_guess(&mut self, id: usize, input: &str) {
+        if self.mode == Mode::Turns && self.current_player().map(|p| p.id) != Some(id) {
+            self.send(id, &ServerMessage::Error(String::from("Not your turn.")));
+            return;
+        }
+        let guess = match input::parse_guess(input, &self.game) {
+            Ok(guess) => guess,
+            Err(err) => {
+                self.send(id, &ServerMessage::Error(err.to_string()));
+                return;
+            }
+        };
+
+        let outcome = self.game.guess(guess);
+        let (name, attempts) = match self.players.iter_mut().find(|p| p.id == id) {
+            Some(player) => {
+                player.attempts += 1;
+                (player.name.clone(), player.attempts)
+            }
+            None => return,
+        };
+
+        match outcome {
+            Outcome::Win => {
+                self.broadcast(&ServerMessage::Winner { id, name, guess, attempts });
+                self.new_round();
+            }
+            Outcome::Lose => {
+                let secret = self.game.secret_number();
+                self.broadcast(&ServerMessage::Lost { secret });
+                self.new_round();
+            }
+            Outcome::TooSmall | Outcome::TooBig => {
+                self.broadcast(&ServerMessage::Guessed { id, name, guess, outcome });
+                if self.mode == Mode::Turns && !self.players.is_empty() {
+                    self.turn = (self.turn + 1) % self.players.len();
+                    self.announce_turn();
+                }
+            }
+        }
+    }
+}
+
+fn handle_client(server: Arc<Mutex<Server>>, stream: TcpStream) {
+    // We need three handles for the socket: one for reading here, one
+    // for the writer thread and one in the server state to disconnect
+    // the player.
+    let (mut reader, writer) = match (stream.try_clone(), stream.try_clone()) {
+        (Ok(reader), Ok(writer)) => (BufReader::new(reader), writer),
+        _ => return,
+    };
+    let (queue, messages) = mpsc::sync_channel(QUEUE_SIZE);
+    thread::spawn(move || write_messages(writer, messages));
+    let id = server.lock().unwrap().join(stream, queue);
+    // Stop at the end of the input, on read errors and too long lines.
+    while let Ok(Some(line)) = read_line(&mut reader) {
+        let mut server = server.lock().unwrap();
+        match line.parse() {
+            Ok(message) => {
+                if !server.handle(id, message) {
+                    break;
+                }
+            }
+            Err(err) => server.send(id, &ServerMessage::Error(err)),
+        }
+    }
+    server.lock().unwrap().leave(id);
+}
+
+// Run the game server, each client is handled in a separate thread.
+pub fn serve<A: ToSocketAddrs>(
+    addr: A,
+    mode: Mode,
+    config: Config,
+    source: Box<dyn SecretSource + Send>,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Listening on {} ({} mode).", listener.local_addr()?, mode);
+    run(listener, mode, config, source);
+    Ok(())
+}
+
+// Accept the clients on the already bound listener (for example, bound
+// to port 0 to get any free port).
+pub fn run(listener: TcpListener, mode: Mode, config: Config, source: Box<dyn SecretSource + Send>) {
+    // `Arc` (atomic reference counter) allows to share the state
+    // between threads and `Mutex` makes sure only one thread changes
+    // it at a time.
+    let server = Arc::new(Mutex::new(Server::new(mode, config, source)));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let server = Arc::clone(&server);
+                thread::spawn(move || handle_client(server, stream));
+            }
+            Err(err) => eprintln!("Failed to accept connection: {}", err),
+        }
+    }
+}
+
+// Connect to the server and play: the stdin lines are sent as guesses
+// and the server messages are printed to stdout.
+pub fn connect<A: ToSocketAddrs>(addr: A, name: &str) -> io::Result<()> {
+    let mut stream = TcpStream::connect(addr)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    writeln!(stream, "{}", ClientMessage::Name(name.to_string()))?;
+
+    // Print the server messages in a separate thread, so we can wait
+    // for the user input at the same time.
+    let printer = thread::spawn(move || {
+        let mut my_id = None;
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            match line.parse::<ServerMessage>() {
+                Ok(message) => {
+                    if let ServerMessage::Welcome { id, .. } = message {
+                        my_id = Some(id);
+                    }
+                    println!("{}", describe(&message, my_id));
+                }
+                Err(_) => println!("{}", line),
+            }
+        }
+        println!("Disconnected from the server.");
+    });
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let message = match line.trim() {
+            "" => continue,
+            "quit" | "QUIT" => ClientMessage::Quit,
+            guess => ClientMessage::Guess(guess.to_string()),
+        };
+        writeln!(stream, "{}", message)?;
+        if message == ClientMessage::Quit {
+            break;
+        }
+    }
+    // Closing our side of the connection makes the server remove the
+    // player and close the connection, then the printer thread stops.
+    stream.shutdown(Shutdown::Write)?;
+    let _ = printer.join();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Always the same secret number.
+    struct Fixed(u32);
+
+    impl SecretSource for Fixed {
+        fn secret_number(&mut self, _min: u32, _max: u32) -> u32 {
+            self.0
+        }
+    }
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(addr: &str) -> Client {
+            let writer = TcpStream::connect(addr).unwrap();
+            // Fail the test instead of hanging if a message doesn't come.
+            writer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            Client { reader: BufReader::new(writer.try_clone().unwrap()), writer }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn expect(&mut self, expected: &str) {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            assert_eq!(line.trim_end(), expected);
+        }
+    }
+
+    fn start(mode: Mode) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let config = Config { min: 1, max: 10, max_attempts: None };
+        thread::spawn(move || run(listener, mode, config, Box::new(Fixed(7))));
+        addr
+    }
+
+    #[test]
+    fn turns_game() {
+        let addr = start(Mode::Turns);
+        let mut ann = Client::connect(&addr);
+        ann.expect("WELCOME 1 1 10 turns");
+        ann.expect("JOINED 1 player1");
+        ann.expect("TURN 1 player1");
+        ann.send("NAME ann");
+        ann.expect("NAME 1 ann");
+
+        let mut bob = Client::connect(&addr);
+        bob.expect("WELCOME 2 1 10 turns");
+        bob.expect("JOINED 2 player2");
+        bob.expect("TURN 1 ann");
+        ann.expect("JOINED 2 player2");
+
+        bob.send("GUESS 5");
+        bob.expect("ERROR Not your turn.");
+        ann.send("GUESS 3");
+        for client in [&mut ann, &mut bob].iter_mut() {
+            client.expect("GUESSED 1 ann 3 too_small");
+            client.expect("TURN 2 player2");
+        }
+        ann.send("GUESS 4");
+        ann.expect("ERROR Not your turn.");
+        bob.send("7");
+        for client in [&mut ann, &mut bob].iter_mut() {
+            client.expect("WINNER 2 player2 7 1");
+            client.expect("ROUND 2");
+            client.expect("TURN 2 player2");
+        }
+
+        bob.send("QUIT");
+        ann.expect("LEFT 2 player2");
+        ann.expect("TURN 1 ann");
+    }
+
+    #[test]
+    fn race_game() {
+        let addr = start(Mode::Race);
+        let mut ann = Client::connect(&addr);
+        ann.expect("WELCOME 1 1 10 race");
+        ann.expect("JOINED 1 player1");
+        let mut bob = Client::connect(&addr);
+        bob.expect("WELCOME 2 1 10 race");
+        bob.expect("JOINED 2 player2");
+        ann.expect("JOINED 2 player2");
+
+        bob.send("11");
+        bob.expect("ERROR 11 is out of range, the number is between 1 and 10.");
+        bob.send("9");
+        bob.expect("GUESSED 2 player2 9 too_big");
+        ann.expect("GUESSED 2 player2 9 too_big");
+        ann.send("7");
+        for client in [&mut ann, &mut bob].iter_mut() {
+            client.expect("WINNER 1 player1 7 1");
+            client.expect("ROUND 2");
+        }
+    }
+
+    #[test]
+    fn parses_client_messages() {
+        assert_eq!("NAME ann".parse(), Ok(ClientMessage::Name(String::from("ann"))));
+        assert_eq!("GUESS 5".parse(), Ok(ClientMessage::Guess(String::from("5"))));
+        assert_eq!(" 5 ".parse(), Ok(ClientMessage::Guess(String::from("5"))));
+        assert_eq!("QUIT".parse(), Ok(ClientMessage::Quit));
+        // An empty name is an error, not a guess.
+        assert_eq!("NAME".parse::<ClientMessage>(), Err(String::from("empty name")));
+        assert_eq!("NAME   ".parse::<ClientMessage>(), Err(String::from("empty name")));
+        assert_eq!("  ".parse::<ClientMessage>(), Err(String::from("empty message")));
+    }
+
+    #[test]
+    fn reads_limited_lines() {
+        let long = "x".repeat(MAX_LINE);
+        let text = format!("GUESS 5\r\n{}\nlast", long);
+        let mut reader = text.as_bytes();
+        assert_eq!(read_line(&mut reader).unwrap(), Some(String::from("GUESS 5")));
+        assert_eq!(read_line(&mut reader).unwrap(), Some(long.clone()));
+        assert_eq!(read_line(&mut reader).unwrap(), Some(String::from("last")));
+        assert_eq!(read_line(&mut reader).unwrap(), None);
+
+        // One byte more, with or without the line end.
+        for text in &[format!("{}x\n", long), format!("{}x", long), format!("{}xx\r\n", long)] {
+            let err = read_line(&mut text.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn long_line_disconnects_client() {
+        let addr = start(Mode::Race);
+        let mut ann = Client::connect(&addr);
+        ann.expect("WELCOME 1 1 10 race");
+        ann.expect("JOINED 1 player1");
+        let mut bob = Client::connect(&addr);
+        bob.expect("WELCOME 2 1 10 race");
+        bob.expect("JOINED 2 player2");
+        ann.expect("JOINED 2 player2");
+
+        // A line that never ends.
+        bob.writer.write_all("1".repeat(10 * MAX_LINE).as_bytes()).unwrap();
+        ann.expect("LEFT 2 player2");
+        // The server closes the connection, the unread part of the line
+        // can make it a reset instead of the end of the input.
+        let mut rest = String::new();
+        let _ = bob.reader.read_to_string(&mut rest);
+        assert_eq!(rest, "");
+
+        // The other player can still play.
+        ann.send("NAME");
+        ann.expect("ERROR empty name");
+        ann.send("7");
+        ann.expect("WINNER 1 player1 7 1");
+    }
+
+    #[test]
+    fn slow_client_is_disconnected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        // Nobody takes the messages from the queue, like a writer
+        // thread blocked on a client that doesn't read.
+        let (queue, _messages) = mpsc::sync_channel(1);
+        let player = Player { id: 1, name: String::from("slow"), stream, queue, attempts: 0 };
+        player.send(&ServerMessage::Round(1));
+        player.send(&ServerMessage::Round(2));
+
+        let mut received = String::new();
+        client.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "");
+    }
+}
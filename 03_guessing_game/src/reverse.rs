@@ -0,0 +1,173 @@
+// Reverse game: the player thinks of a number and the computer guesses
+// it, the player answers whether the number is higher, lower or equal
+// to the guess.
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+
+use game::Config;
+use solver::Strategy;
+
+// The answers contradict each other: there is no number that matches
+// all of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inconsistent {
+    pub guess: u32,
+    pub answer: Ordering,
+    // The interval where the number must be according to the previous
+    // answers.
+    pub low: u32,
+    pub high: u32,
+}
+
+impl fmt::Display for Inconsistent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = if self.answer == Ordering::Less { "lower" } else { "higher" };
+        write!(f, "Your answers are inconsistent: the number must be between {} and {}, \
+                   so it can not be {} than {}.",
+               self.low, self.high, direction, self.guess)
+    }
+}
+
+impl error::Error for Inconsistent {}
+
+pub struct ReverseGame<S: Strategy> {
+    strategy: S,
+    low: u32,
+    high: u32,
+    guess: Option<u32>,
+    attempts: u32,
+}
+
+impl<S: Strategy> ReverseGame<S> {
+    pub fn new(config: Config, strategy: S) -> ReverseGame<S> {
+        ReverseGame { strategy, low: config.min, high: config.max, guess: None, attempts: 0 }
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    // The next guess, it is always inside of the interval allowed by
+    // the previous answers.
+    pub fn next_guess(&mut self) -> u32 {
+        let guess = self.strategy.next_guess(self.low, self.high);
+        self.guess = Some(guess);
+        self.attempts += 1;
+        guess
+    }
+
+    // Take the answer for the last guess: how the player's number
+    // compares to the guess (the same `Ordering` as returned by
+    // `number.cmp(&guess)`).
+    //
+    // Returns the number if it is found and an error if the answer
+    // contradicts the previous ones.
+    pub fn answer(&mut self, answer: Ordering) -> Result<Option<u32>, Inconsistent> {
+        let guess = match self.guess {
+            Some(guess) => guess,
+            // No guess yet, nothing to answer to.
+            None => return Ok(None),
+        };
+        let inconsistent = Inconsistent { guess, answer, low: self.low, high: self.high };
+        match answer {
+            Ordering::Equal => return Ok(Some(guess)),
+            Ordering::Less => {
+                if guess <= self.low {
+                    return Err(inconsistent);
+                }
+                self.high = guess - 1;
+            }
+            Ordering::Greater => {
+                if guess >= self.high {
+                    return Err(inconsistent);
+                }
+                self.low = guess + 1;
+            }
+        }
+        Ok(None)
+    }
+}
+
+// Parse the player's answer, `None` if the answer is not recognized.
+pub fn parse_answer(input: &str) -> Option<Ordering> {
+    match input.trim().to_lowercase().as_str() {
+        "h" | "higher" | "bigger" | ">" => Some(Ordering::Greater),
+        "l" | "lower" | "smaller" | "<" => Some(Ordering::Less),
+        "c" | "correct" | "yes" | "=" => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver::BinarySearch;
+
+    fn game() -> ReverseGame<BinarySearch> {
+        ReverseGame::new(Config { min: 1, max: 100, max_attempts: None }, BinarySearch)
+    }
+
+    // Answer honestly for the given number, returns the found number.
+    fn play(game: &mut ReverseGame<BinarySearch>, number: u32) -> u32 {
+        loop {
+            let guess = game.next_guess();
+            if let Some(found) = game.answer(number.cmp(&guess)).unwrap() {
+                return found;
+            }
+        }
+    }
+
+    #[test]
+    fn finds_number() {
+        for &number in &[1, 34, 50, 99, 100] {
+            let mut game = game();
+            assert_eq!(play(&mut game, number), number);
+            assert!(game.attempts() <= 7);
+        }
+    }
+
+    #[test]
+    fn detects_inconsistent_answers() {
+        let mut game = game();
+        let first = game.next_guess();
+        assert_eq!(game.answer(Ordering::Greater), Ok(None));
+        let second = game.next_guess();
+        assert!(second > first);
+        assert_eq!(game.answer(Ordering::Less), Ok(None));
+        // The number is between the two guesses, answer "lower" until
+        // the interval can not shrink anymore.
+        let error = loop {
+            game.next_guess();
+            if let Err(error) = game.answer(Ordering::Less) {
+                break error;
+            }
+        };
+        assert_eq!(error.answer, Ordering::Less);
+        // The guess is the smallest possible number, so it can not be
+        // lower.
+        assert_eq!(error.low, first + 1);
+        assert_eq!(error.guess, error.low);
+        assert!(error.high < second);
+    }
+
+    #[test]
+    fn number_can_not_be_outside_of_range() {
+        let mut game = ReverseGame::new(Config { min: 1, max: 1, max_attempts: None }, BinarySearch);
+        assert_eq!(game.next_guess(), 1);
+        let error = game.answer(Ordering::Greater).unwrap_err();
+        assert_eq!(error, Inconsistent { guess: 1, answer: Ordering::Greater, low: 1, high: 1 });
+        assert_eq!(
+            error.to_string(),
+            "Your answers are inconsistent: the number must be between 1 and 1, so it can not be higher than 1."
+        );
+    }
+
+    #[test]
+    fn parses_answers() {
+        assert_eq!(parse_answer(" H\n"), Some(Ordering::Greater));
+        assert_eq!(parse_answer("lower"), Some(Ordering::Less));
+        assert_eq!(parse_answer("="), Some(Ordering::Equal));
+        assert_eq!(parse_answer("maybe"), None);
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json;
+
+// A single high-score entry.
+// The `Serialize` and `Deserialize` traits are generated by the serde
+// derive macros, so the structure can be saved to / loaded from JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub name: String,
+    pub min: u32,
+    pub max: u32,
+    pub attempts: u32,
+    // Game duration in milliseconds.
+    pub duration_ms: u64,
+}
+
+impl Score {
+    pub fn new(name: &str, min: u32, max: u32, attempts: u32, duration: Duration) -> Score {
+        Score {
+            name: name.to_string(),
+            min,
+            max,
+            attempts,
+            duration_ms: duration.as_secs() * 1000 + u64::from(duration.subsec_millis()),
+        }
+    }
+}
+
+// High-score table, stored as a JSON file.
+// Scores are kept sorted: fewer attempts first, faster games first
+// for the same number of attempts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScoreBoard {
+    scores: Vec<Score>,
+}
+
+impl ScoreBoard {
+    // Load the table from the file, missing file means no scores yet.
+    pub fn load(path: &Path) -> io::Result<ScoreBoard> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(ScoreBoard::default()),
+            Err(e) => return Err(e),
+        };
+        let mut board: ScoreBoard = serde_json::from_reader(file)?;
+        board.sort();
+        Ok(board)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, score: Score) {
+        self.scores.push(score);
+        self.sort();
+    }
+
+    pub fn scores(&self) -> &[Score] {
+        &self.scores
+    }
+
+    // Best scores for the given range.
+    pub fn top(&self, min: u32, max: u32, count: usize) -> Vec<&Score> {
+        self.scores.iter()
+            .filter(|score| score.min == min && score.max == max)
+            .take(count)
+            .collect()
+    }
+
+    fn sort(&mut self) {
+        self.scores.sort_by_key(|score| (score.attempts, score.duration_ms));
+    }
+}
+
+// Format scores as a table, one score per line.
+pub fn format_table<'a, I: IntoIterator<Item = &'a Score>>(scores: I) -> String {
+    let mut table = format!("{:>3}  {:<16} {:>11} {:>8} {:>9}\n", "#", "Name", "Range", "Attempts", "Time");
+    for (i, score) in scores.into_iter().enumerate() {
+        let range = format!("{}-{}", score.min, score.max);
+        let time = format!("{:.1}s", score.duration_ms as f64 / 1000.0);
+        table.push_str(&format!(
+            "{:>3}  {:<16} {:>11} {:>8} {:>9}\n", i + 1, score.name, range, score.attempts, time));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    // Each test uses its own file, the tests run in parallel.
+    fn scores_file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("guessing_game_scores_{}_{}.json", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn score(name: &str, attempts: u32, duration_ms: u64) -> Score {
+        Score::new(name, 1, 100, attempts, Duration::from_millis(duration_ms))
+    }
+
+    #[test]
+    fn sorts_by_attempts_and_time() {
+        let mut board = ScoreBoard::default();
+        board.add(score("slow", 5, 3000));
+        board.add(score("fast", 5, 1500));
+        board.add(score("lucky", 1, 9000));
+        board.add(Score::new("hard", 1, 1000, 3, Duration::from_millis(100)));
+        let names: Vec<&str> = board.scores().iter().map(|score| score.name.as_str()).collect();
+        assert_eq!(names, ["lucky", "hard", "fast", "slow"]);
+        let top: Vec<&str> = board.top(1, 100, 2).iter().map(|score| score.name.as_str()).collect();
+        assert_eq!(top, ["lucky", "fast"]);
+        assert!(board.top(1, 10, 5).is_empty());
+    }
+
+    #[test]
+    fn saves_and_loads_json() {
+        let path = scores_file("round_trip");
+        let mut board = ScoreBoard::default();
+        board.add(score("alice", 4, 2500));
+        board.add(score("bob", 7, 1234));
+        board.save(&path).unwrap();
+        let loaded = ScoreBoard::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.scores(), board.scores());
+        assert_eq!(loaded.scores()[0].duration_ms, 2500);
+    }
+
+    #[test]
+    fn load_sorts_scores() {
+        let path = scores_file("unsorted");
+        let json = r#"{"scores": [
+            {"name": "b", "min": 1, "max": 100, "attempts": 9, "duration_ms": 10},
+            {"name": "a", "min": 1, "max": 100, "attempts": 2, "duration_ms": 10}
+        ]}"#;
+        fs::write(&path, json).unwrap();
+        let loaded = ScoreBoard::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.scores()[0].name, "a");
+    }
+
+    #[test]
+    fn missing_file_is_empty_board() {
+        let path = scores_file("missing");
+        let board = ScoreBoard::load(&path).unwrap();
+        assert!(board.scores().is_empty());
+        // Loading doesn't create the file.
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let path = scores_file("corrupt");
+        let files = [
+            ("not json", ErrorKind::InvalidData),
+            ("{\"scores\": [{\"name\": \"a\"}]}", ErrorKind::InvalidData),
+            // Truncated file, like after a failed save.
+            ("{\"scores\": [", ErrorKind::UnexpectedEof),
+        ];
+        for &(content, kind) in &files {
+            fs::write(&path, content).unwrap();
+            let err = ScoreBoard::load(&path).unwrap_err();
+            assert_eq!(err.kind(), kind, "{}", content);
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn formats_table() {
+        let table = format_table(&[score("alice", 4, 2500)]);
+        assert_eq!(
+            table,
+            "  #  Name                   Range Attempts      Time\n  \
+             1  alice                  1-100        4      2.5s\n"
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use rand::{self, IsaacRng, Rng, SeedableRng};
+
+// Source of the secret number.
+//
+// The game only needs a number in the `min..=max` range, so everything
+// that can produce it can be plugged into the game: a random number
+// generator, a seeded (reproducible) generator or, for example, a fixed
+// list of numbers.
+pub trait SecretSource {
+    fn secret_number(&mut self, min: u32, max: u32) -> u32;
+}
+
+// Random numbers from the thread-local generator (seeded by the OS),
+// this is what the game uses by default.
+pub struct RandomSource;
+
+impl SecretSource for RandomSource {
+    fn secret_number(&mut self, min: u32, max: u32) -> u32 {
+        // Note: `gen_range` upper bound is exclusive.
+        rand::thread_rng().gen_range(min, max + 1)
+    }
+}
+
+// Reproducible random numbers: the same seed always produces the same
+// sequence of secret numbers.
+//
+// We use the ISAAC generator (and not `StdRng`) because it is seeded
+// with `u32` values and produces the same numbers on 32-bit and 64-bit
+// platforms.
+pub struct SeededSource {
+    rng: IsaacRng,
+}
+
+impl SeededSource {
+    pub fn new(seed: u64) -> SeededSource {
+        let seed = [seed as u32, (seed >> 32) as u32];
+        SeededSource { rng: IsaacRng::from_seed(&seed[..]) }
+    }
+}
+
+impl SecretSource for SeededSource {
+    fn secret_number(&mut self, min: u32, max: u32) -> u32 {
+        self.rng.gen_range(min, max + 1)
+    }
+}
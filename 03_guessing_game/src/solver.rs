@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use game::{Config, Game, Outcome};
+use secret::SecretSource;
+
+// A computer player.
+//
+// The strategy gets the interval where the secret number is (it is
+// narrowed down by the game after each "too small" / "too big" answer)
+// and returns the next guess.
+pub trait Strategy {
+    fn name(&self) -> &str;
+
+    fn next_guess(&mut self, low: u32, high: u32) -> u32;
+}
+
+// Guess in the middle of the interval, this halves the interval on
+// each step.
+pub struct BinarySearch;
+
+impl Strategy for BinarySearch {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn next_guess(&mut self, low: u32, high: u32) -> u32 {
+        // Same as `(low + high) / 2`, but can not overflow.
+        low + (high - low) / 2
+    }
+}
+
+// Guess a random number from the interval.
+// Any `SecretSource` can be used as a random number generator, so the
+// strategy can be made reproducible with the `SeededSource`.
+pub struct RandomGuess<S: SecretSource> {
+    source: S,
+}
+
+impl<S: SecretSource> RandomGuess<S> {
+    pub fn new(source: S) -> RandomGuess<S> {
+        RandomGuess { source }
+    }
+}
+
+impl<S: SecretSource> Strategy for RandomGuess<S> {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_guess(&mut self, low: u32, high: u32) -> u32 {
+        self.source.secret_number(low, high)
+    }
+}
+
+// Try all numbers one by one, starting from the smallest one.
+pub struct LinearScan;
+
+impl Strategy for LinearScan {
+    fn name(&self) -> &str {
+        "linear"
+    }
+
+    fn next_guess(&mut self, low: u32, _high: u32) -> u32 {
+        low
+    }
+}
+
+// Let the strategy play the game until it is finished.
+pub fn play<S: Strategy + ?Sized>(game: &mut Game, strategy: &mut S) -> Outcome {
+    loop {
+        let (low, high) = game.interval();
+        let outcome = game.guess(strategy.next_guess(low, high));
+        if game.is_finished() {
+            return outcome;
+        }
+    }
+}
+
+// Statistics over a number of simulated games.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub games: u32,
+    pub wins: u32,
+    pub total_attempts: u64,
+    pub max_attempts: u32,
+    // Number of games (value) finished in the given number of
+    // attempts (key), `BTreeMap` keeps the keys sorted.
+    pub distribution: BTreeMap<u32, u32>,
+}
+
+impl Stats {
+    pub fn add(&mut self, game: &Game, outcome: Outcome) {
+        self.games += 1;
+        if outcome == Outcome::Win {
+            self.wins += 1;
+        }
+        self.total_attempts += u64::from(game.attempts());
+        self.max_attempts = self.max_attempts.max(game.attempts());
+        *self.distribution.entry(game.attempts()).or_insert(0) += 1;
+    }
+
+    pub fn mean_attempts(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.total_attempts as f64 / f64::from(self.games)
+    }
+}
+
+// Statistics as a text report with the distribution histogram.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "games: {}, wins: {}, mean attempts: {:.2}, max attempts: {}",
+                 self.games, self.wins, self.mean_attempts(), self.max_attempts)?;
+        // Scale the histogram bars to at most 50 characters.
+        let largest = self.distribution.values().cloned().max().unwrap_or(0);
+        for (attempts, count) in &self.distribution {
+            let width = (u64::from(*count) * 50 / u64::from(largest.max(1))) as usize;
+            writeln!(f, "{:>6} | {:<50} {}", attempts, "#".repeat(width.max(1)), count)?;
+        }
+        Ok(())
+    }
+}
+
+// Play `games` games with the strategy, secret numbers are taken from
+// the `source`.
+pub fn simulate<S, T>(config: Config, source: &mut S, strategy: &mut T, games: u32) -> Stats
+where
+    S: SecretSource + ?Sized,
+    T: Strategy + ?Sized,
+{
+    let mut stats = Stats::default();
+    for _ in 0..games {
+        let mut game = Game::with_source(config, source);
+        let outcome = play(&mut game, strategy);
+        stats.add(&game, outcome);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use analysis::optimal_attempts;
+    use secret::SeededSource;
+
+    // Secret numbers from the list, in order.
+    struct Fixed(Vec<u32>);
+
+    impl SecretSource for Fixed {
+        fn secret_number(&mut self, _min: u32, _max: u32) -> u32 {
+            self.0.remove(0)
+        }
+    }
+
+    fn config(min: u32, max: u32) -> Config {
+        Config { min, max, max_attempts: None }
+    }
+
+    #[test]
+    fn binary_search_is_within_optimal_attempts() {
+        for &(min, max) in &[(1, 1), (1, 2), (1, 3), (0, 7), (1, 10), (1, 100), (1, 1000), (5, 1028)] {
+            let optimal = optimal_attempts(min, max);
+            for secret in min..max + 1 {
+                let mut game = Game::with_secret(config(min, max), secret);
+                assert_eq!(play(&mut game, &mut BinarySearch), Outcome::Win);
+                assert!(game.attempts() <= optimal, "{}..{}: {} took {}", min, max, secret, game.attempts());
+            }
+            // Some secret needs all the attempts.
+            let stats = simulate(config(min, max), &mut Fixed((min..max + 1).collect()), &mut BinarySearch, max - min + 1);
+            assert_eq!(stats.max_attempts, optimal);
+        }
+    }
+
+    #[test]
+    fn binary_search_in_large_range() {
+        // The middle is computed without overflow at the top of the range.
+        let (min, max) = (0, u32::MAX - 1);
+        let mut secrets = vec![min, max, max - 1, 1, u32::MAX / 2];
+        let mut source = SeededSource::new(42);
+        secrets.extend((0..100).map(|_| source.secret_number(min, max)));
+        let games = secrets.len() as u32;
+        let stats = simulate(config(min, max), &mut Fixed(secrets), &mut BinarySearch, games);
+        assert_eq!(stats.wins, games);
+        assert!(stats.max_attempts <= optimal_attempts(min, max));
+        assert_eq!(optimal_attempts(min, max), 32);
+    }
+
+    #[test]
+    fn linear_scan_and_random_always_win() {
+        let mut game = Game::with_secret(config(1, 100), 42);
+        assert_eq!(play(&mut game, &mut LinearScan), Outcome::Win);
+        assert_eq!(game.attempts(), 42);
+        let mut strategy = RandomGuess::new(SeededSource::new(7));
+        let stats = simulate(config(1, 100), &mut SeededSource::new(1), &mut strategy, 100);
+        assert_eq!(stats.wins, 100);
+        assert!(stats.max_attempts <= 100);
+    }
+
+    #[test]
+    fn stats_aggregate_games() {
+        let config = Config { min: 1, max: 10, max_attempts: Some(3) };
+        let mut stats = Stats::default();
+        assert_eq!(stats.mean_attempts(), 0.0);
+        // Linear scan: 1 attempt for 1, 2 for 2, lost after 3 for 9 and 10.
+        for &secret in &[1, 2, 2, 9, 10] {
+            let mut game = Game::with_secret(config, secret);
+            let outcome = play(&mut game, &mut LinearScan);
+            stats.add(&game, outcome);
+        }
+        let mut distribution = BTreeMap::new();
+        distribution.insert(1, 1);
+        distribution.insert(2, 2);
+        distribution.insert(3, 2);
+        assert_eq!(stats, Stats { games: 5, wins: 3, total_attempts: 11, max_attempts: 3, distribution });
+        assert_eq!(stats.mean_attempts(), 2.2);
+
+        // Same as playing the games with `simulate`.
+        let mut source = Fixed(vec![1, 2, 2, 9, 10]);
+        assert_eq!(simulate(config, &mut source, &mut LinearScan, 5), stats);
+    }
+
+    #[test]
+    fn stats_report() {
+        let mut source = Fixed(vec![1, 2, 2, 2]);
+        let stats = simulate(config(1, 10), &mut source, &mut LinearScan, 4);
+        let report = stats.to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "games: 4, wins: 4, mean attempts: 1.75, max attempts: 2");
+        // The largest bar is 50 characters, the other ones are scaled.
+        assert_eq!(lines[1], format!("{:>6} | {:<50} {}", 1, "#".repeat(16), 1));
+        assert_eq!(lines[2], format!("{:>6} | {} {}", 2, "#".repeat(50), 3));
+        assert_eq!(lines.len(), 3);
+    }
+}
@@ -0,0 +1,117 @@
+// Run the game binary with a fixed seed and piped input, and compare
+// the whole output: with `--seed 42` the secret number is always 34.
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// Each test uses its own scores file, so the tests don't see each
+// other's high scores.
+fn scores_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("guessing_game_cli_{}_{}.json", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn play(name: &str, args: &[&str], input: &str) -> String {
+    let scores = scores_file(name);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_guessing_game"))
+        .args(["--seed", "42", "--name", "tester", "--scores-file"])
+        .arg(&scores)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run the game");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = fs::remove_file(&scores);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn win() {
+    let output = play("win", &[], "20\nabc\n101\n20\n50\n34\n");
+    assert_eq!(
+        output,
+        "Guess the number!\n\
+         The number is between 1 and 100.\n\
+         Please input your guess.\n\
+         You guessed: 20\n\
+         Too small!\n\
+         'abc' is not a number, please input a number.\n\
+         101 is out of range, the number is between 1 and 100.\n\
+         You already tried 20, try another number.\n\
+         You guessed: 50\n\
+         Too big!\n\
+         You guessed: 34\n\
+         You win!\n\
+         You used 3 attempts, binary search needs at most 7 - lucky!\n"
+    );
+}
+
+#[test]
+fn lose() {
+    let output = play("lose", &["--attempts", "2"], "90\n10\n");
+    assert_eq!(
+        output,
+        "Guess the number!\n\
+         The number is between 1 and 100.\n\
+         You have 2 attempts.\n\
+         Please input your guess.\n\
+         You guessed: 90\n\
+         Too big!\n\
+         You guessed: 10\n\
+         You lose! The number was 34.\n\
+         You used 2 attempts, binary search needs at most 7 - lucky!\n"
+    );
+}
+
+#[test]
+fn end_of_input() {
+    let output = play("eof", &[], "50\n");
+    assert_eq!(
+        output,
+        "Guess the number!\n\
+         The number is between 1 and 100.\n\
+         Please input your guess.\n\
+         You guessed: 50\n\
+         Too big!\n\
+         No more input.\n\
+         Bye! The number was 34.\n"
+    );
+}
+
+#[test]
+fn hints() {
+    let output = play("hints", &["--hints", "--min", "1", "--max", "10"], "5\n2\n4\n");
+    assert_eq!(
+        output,
+        "Guess the number!\n\
+         The number is between 1 and 10.\n\
+         Please input your guess.\n\
+         You guessed: 5\n\
+         Too big!\n\
+         Hint: the number is between 1 and 4.\n\
+         You guessed: 2\n\
+         Too small!\n\
+         Hint: the number is between 3 and 4.\n\
+         You guessed: 4\n\
+         You win!\n\
+         You used 3 attempts, binary search needs at most 4 - lucky!\n"
+    );
+}
+
+#[test]
+fn empty_name() {
+    let output = Command::new(env!("CARGO_BIN_EXE_guessing_game"))
+        .args(["--name", " "])
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run the game");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "--name should not be empty\n");
+    assert!(output.stdout.is_empty());
+}
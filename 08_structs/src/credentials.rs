@@ -0,0 +1,257 @@
+// Password storage: we never store the password itself, only a salted
+// hash of it (PBKDF2 with HMAC-SHA256).
+//
+// The salt is a random value stored with the hash, it makes the hashes
+// of the same password different for different users. PBKDF2 repeats the
+// hashing many times (iterations), this makes brute-force attacks slow.
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use getrandom;
+use pbkdf2::pbkdf2_hmac;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use sha2::Sha256;
+
+// Number of iterations for new hashes (OWASP recommendation for
+// PBKDF2-HMAC-SHA256), stored with each hash, so it can be increased
+// later and old hashes still work.
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+const SALT_LENGTH: usize = 16;
+const HASH_LENGTH: usize = 32;
+const ALGORITHM: &str = "pbkdf2-sha256";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CredentialError {
+    PasswordTooShort,
+    WrongPassword,
+    NoPassword,
+    // The stored hash can not be parsed.
+    InvalidHash,
+    // The OS random number generator failed.
+    Random(String),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CredentialError::PasswordTooShort => {
+                write!(f, "password should be at least {} characters long", MIN_PASSWORD_LENGTH)
+            }
+            CredentialError::WrongPassword => write!(f, "wrong password"),
+            CredentialError::NoPassword => write!(f, "password is not set"),
+            CredentialError::InvalidHash => write!(f, "invalid password hash"),
+            CredentialError::Random(ref err) => write!(f, "failed to generate salt: {}", err),
+        }
+    }
+}
+
+impl error::Error for CredentialError {}
+
+#[derive(Clone, PartialEq)]
+pub struct PasswordHash {
+    iterations: u32,
+    salt: [u8; SALT_LENGTH],
+    hash: [u8; HASH_LENGTH],
+}
+
+impl PasswordHash {
+    pub fn new(password: &str) -> Result<PasswordHash, CredentialError> {
+        PasswordHash::with_iterations(password, DEFAULT_ITERATIONS)
+    }
+
+    pub fn with_iterations(password: &str, iterations: u32) -> Result<PasswordHash, CredentialError> {
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(CredentialError::PasswordTooShort);
+        }
+        let mut salt = [0u8; SALT_LENGTH];
+        getrandom::getrandom(&mut salt).map_err(|err| CredentialError::Random(err.to_string()))?;
+        Ok(PasswordHash { iterations, salt, hash: compute(password, &salt, iterations) })
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        let hash = compute(password, &self.salt, self.iterations);
+        // Compare all bytes even if the first ones differ, so the time
+        // of the check doesn't tell how many bytes are correct.
+        hash.iter().zip(self.hash.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    // The hash was created with fewer iterations than we use now, it
+    // should be replaced (we can do it when the user logs in, as only
+    // then we know the password).
+    pub fn needs_rehash(&self) -> bool {
+        self.iterations < DEFAULT_ITERATIONS
+    }
+}
+
+fn compute(password: &str, salt: &[u8], iterations: u32) -> [u8; HASH_LENGTH] {
+    let mut hash = [0u8; HASH_LENGTH];
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut hash);
+    hash
+}
+
+// The hash should not appear in logs, so the `Debug` output hides it
+// (instead of `#[derive(Debug)]` we implement it manually).
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PasswordHash(<redacted>)")
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str, out: &mut [u8]) -> Result<(), CredentialError> {
+    if hex.len() != out.len() * 2 || !hex.is_ascii() {
+        return Err(CredentialError::InvalidHash);
+    }
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| CredentialError::InvalidHash)?;
+    }
+    Ok(())
+}
+
+// Storage format: `pbkdf2-sha256$<iterations>$<salt hex>$<hash hex>`.
+// This is `Display` and not `Debug`, so it has to be used explicitly.
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}${}${}${}", ALGORITHM, self.iterations, to_hex(&self.salt), to_hex(&self.hash))
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = CredentialError;
+
+    fn from_str(s: &str) -> Result<PasswordHash, CredentialError> {
+        let parts: Vec<&str> = s.split('$').collect();
+        if parts.len() != 4 || parts[0] != ALGORITHM {
+            return Err(CredentialError::InvalidHash);
+        }
+        let iterations = parts[1].parse().map_err(|_| CredentialError::InvalidHash)?;
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut hash = [0u8; HASH_LENGTH];
+        from_hex(parts[2], &mut salt)?;
+        from_hex(parts[3], &mut hash)?;
+        Ok(PasswordHash { iterations, salt, hash })
+    }
+}
+
+// Save the hash as a string in the storage format.
+impl Serialize for PasswordHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PasswordHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PasswordHash, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use session::{ManualClock, SessionError, SessionManager};
+    use std::time::{Duration, SystemTime};
+    use user::{build_user, User};
+
+    // Few iterations to keep the tests fast.
+    const ITERATIONS: u32 = 1_000;
+
+    fn user() -> User {
+        build_user(String::from("ann"), String::from("ann@example.com")).unwrap()
+    }
+
+    #[test]
+    fn verifies_password() {
+        let hash = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        assert!(hash.verify("correct horse"));
+        assert!(!hash.verify("correct hors"));
+        assert!(!hash.verify("battery staple"));
+    }
+
+    #[test]
+    fn salts_are_random() {
+        let first = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        let second = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        assert_ne!(first, second);
+        assert!(second.verify("correct horse"));
+    }
+
+    #[test]
+    fn rejects_short_passwords() {
+        assert_eq!(PasswordHash::with_iterations("short", ITERATIONS), Err(CredentialError::PasswordTooShort));
+    }
+
+    #[test]
+    fn needs_rehash_for_old_iteration_counts() {
+        let old = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        assert!(old.needs_rehash());
+        // Parse instead of computing, hashing with the default iterations
+        // is slow.
+        let current: PasswordHash = format!("{}${}${}${}", ALGORITHM, DEFAULT_ITERATIONS, "00".repeat(SALT_LENGTH), "11".repeat(HASH_LENGTH))
+            .parse()
+            .unwrap();
+        assert!(!current.needs_rehash());
+    }
+
+    #[test]
+    fn string_round_trip() {
+        let hash = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        let text = hash.to_string();
+        assert!(text.starts_with("pbkdf2-sha256$1000$"));
+        let parsed: PasswordHash = text.parse().unwrap();
+        assert_eq!(parsed, hash);
+        assert!(parsed.verify("correct horse"));
+        assert_eq!("pbkdf2-sha256$1000$00$11".parse::<PasswordHash>(), Err(CredentialError::InvalidHash));
+        assert_eq!("md5$1$00$11".parse::<PasswordHash>(), Err(CredentialError::InvalidHash));
+    }
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let hash = PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap();
+        let debug = format!("{:?}", hash);
+        assert_eq!(debug, "PasswordHash(<redacted>)");
+        assert!(!debug.contains(&to_hex(&hash.hash)));
+        assert!(!debug.contains(&to_hex(&hash.salt)));
+    }
+
+    #[test]
+    fn password_login_rotates_old_hashes() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let mut sessions = SessionManager::new(&clock, Duration::from_secs(60));
+        let mut ann = user();
+        assert_eq!(sessions.login_with_password(&mut ann, "correct horse"), Err(SessionError::from(CredentialError::NoPassword)));
+
+        ann.password_hash = Some(PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap());
+        assert_eq!(
+            sessions.login_with_password(&mut ann, "battery staple"),
+            Err(SessionError::from(CredentialError::WrongPassword))
+        );
+        assert!(ann.password_hash.as_ref().unwrap().needs_rehash());
+
+        // The only test hashing with the default iterations (slow).
+        assert!(sessions.login_with_password(&mut ann, "correct horse").is_ok());
+        assert!(!ann.password_hash.as_ref().unwrap().needs_rehash());
+        assert!(ann.verify_password("correct horse").is_ok());
+    }
+
+    #[test]
+    fn change_password_requires_old_password() {
+        let mut ann = user();
+        ann.password_hash = Some(PasswordHash::with_iterations("correct horse", ITERATIONS).unwrap());
+        assert_eq!(ann.change_password("battery staple", "new password"), Err(CredentialError::WrongPassword));
+        assert_eq!(ann.change_password("correct horse", "short"), Err(CredentialError::PasswordTooShort));
+        assert!(ann.verify_password("correct horse").is_ok());
+        // Neither the salt nor the hash is in the debug output.
+        let stored = ann.password_hash.as_ref().unwrap().to_string();
+        let debug = format!("{:?}", ann);
+        assert!(stored.split('$').skip(2).all(|hex| !debug.contains(hex)));
+    }
+}
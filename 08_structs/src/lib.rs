@@ -0,0 +1,27 @@
+// The `User` structure and the code working with it are in the library
+// part of the crate, so they can be reused (the `src/main.rs` binary
+// is one of the users).
+extern crate csv;
+extern crate getrandom;
+extern crate pbkdf2;
+extern crate serde;
+// `#[macro_use]` makes the `Serialize` and `Deserialize` derive macros
+// available in the crate.
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate sha2;
+
+pub mod credentials;
+pub mod repository;
+pub mod session;
+pub mod transfer;
+pub mod units;
+pub mod user;
+
+pub use credentials::{CredentialError, PasswordHash};
+pub use repository::{InMemoryRepository, JsonFileRepository, RepositoryError, UserRepository};
+pub use session::{Clock, ManualClock, SessionError, SessionManager, SystemClock};
+pub use transfer::{Credentials, ImportReport, RowError, TransferError};
+pub use user::{build_user, User, UserError};
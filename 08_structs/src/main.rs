@@ -1,50 +1,25 @@
-// Structure is a collection of fields of various types.
-// The #[derive(Debug)] is to be able to print the struct.
-#[derive(Debug)]
-struct User {
-    username: String,
-    email: String,
-    active: bool,
-    login_count: u64,
-}
+// The `User` structure, its methods and the `build_user` function are
+// defined in the library part of the crate, see `src/user.rs`.
+extern crate generics;
+extern crate structs;
 
-// Define a method of the structure.
-impl User {
-    fn display_name(&self) -> String {
-        self.username.clone()
-    }
-
-    fn display_status(&self) -> String {
-        format!("Status: {}, login count: {}", self.active, self.login_count)
-    }
-}
+use std::time::Duration;
 
-// Constructor-like function with defaults.
-fn build_user(username: String, email: String) -> User {
-    User {
-        username: username,
-        email,  // shorthand syntax, field name same as parameter name
-        active: true,
-        login_count: 0,
-    }
-}
+use generics::Point;
+use structs::units::{Centimeters, Feet, Inches, Kilograms, Minutes, Pounds, Seconds};
+use structs::{build_user, transfer, Credentials, InMemoryRepository, SessionManager, SystemClock, User, UserRepository};
 
 // Tuple struct, without named fields.
 // Note: It is almost always better to use a struct rather than a tuple struct.
-struct Point(i32, i32);
+struct Color(u8, u8, u8);
 
 // Function that works with the structure.
-fn move_point(pt: &Point, dx: i32, dy: i32) -> Point {
-    Point(pt.0 + dx, pt.1 + dy)
+// The `Point<T>` struct comes from `14_generics_and_traits/src/point.rs`,
+// it supports vector operations like `+`, `-`, dot product and rotation.
+fn move_point(pt: &Point<i32>, dx: i32, dy: i32) -> Point<i32> {
+    *pt + Point::new(dx, dy)
 }
 
-// There is one case when a tuple struct is very useful, though, 
-// and that’s a tuple struct with only one element.
-// We call this the ‘newtype’ pattern, because it allows you 
-// to create a new type, distinct from that of its contained 
-// value and expressing its own semantic meaning:
-struct Inches(i32);
-
 // Unit struct, without fields.
 // It behaves similarly to the `()`, the unit type.
 #[derive(Debug)]
@@ -58,6 +33,9 @@ fn main() {
         username: String::from("tester"),
         active: true,
         login_count: 0,
+        logged_in: false,
+        last_login: None,
+        password_hash: None,
     };
 
     user1.email = String::from("test2@example.com");
@@ -68,25 +46,87 @@ fn main() {
     println!("User: {:#?}", user1.display_name());
     println!("User: {:#?}", user1.display_status());
 
-    // Create using constructor-like function:
+    // Create using constructor-like function, it validates the data and
+    // returns `Result`:
     let user2 = build_user(
         String::from("Joe"),
         String::from("joe@example.com"),
-    );
+    ).expect("valid user");
     println!("User: {:?}", user2);
 
+    match build_user(String::from("J"), String::from("joe.example.com")) {
+        Ok(user) => println!("User: {:?}", user),
+        Err(err) => println!("Error: {}", err),
+    }
+
     // Create using struct update syntax:
     let joe2 = User {
         username: String::from("Joe2"),
-        ..user2
+        ..user2.clone()
     };
     println!("User: {:?}", joe2);
 
+    // Store users in the repository:
+    // (there is also `JsonFileRepository` that saves users to a file)
+    let mut repository = InMemoryRepository::new();
+    repository.create(user2).expect("new user");
+    repository.create(joe2.clone()).expect("new user");
+    if let Err(err) = repository.create(joe2) {
+        println!("Error: {}", err);
+    }
+    let mut joe = repository.get("Joe").expect("existing user");
+    joe.email = String::from("joe@example.org");
+    repository.update(joe).expect("existing user");
+    repository.delete("Joe2").expect("existing user");
+    println!("Users: {:?}", repository.list());
+
+    // Log in and out, sessions expire in one hour:
+    let mut sessions = SessionManager::new(SystemClock, Duration::from_secs(3600));
+    let token = sessions.login(&mut user1).expect("active user");
+    println!("{}", user1.display_status());
+    println!("Session: {:?}", sessions.validate(&token));
+    sessions.logout(&token, &mut user1).expect("valid session");
+    sessions.deactivate(&mut user1);
+    println!("{}", user1.display_status());
+    if let Err(err) = sessions.login(&mut user1) {
+        println!("Error: {}", err);
+    }
+
+    // Passwords are stored as salted hashes, the hash is not shown in the
+    // debug output:
+    let mut user3 = build_user(String::from("Ann"), String::from("ann@example.com")).expect("valid user");
+    user3.set_password("correct horse").expect("long password");
+    println!("User: {:?}", user3);
+    println!("Wrong password: {:?}", sessions.login_with_password(&mut user3, "battery staple"));
+    println!("Login: {:?}", sessions.login_with_password(&mut user3, "correct horse").is_ok());
+
+    // Export users to CSV (or JSON) and import them back, the invalid rows
+    // are reported, but don't stop the import:
+    // Password hashes are left out unless `Credentials::Include` is used.
+    let mut exported = Vec::new();
+    transfer::export_csv(&[user1.clone(), user3.clone()], Credentials::Omit, &mut exported).expect("export to memory");
+    let report = transfer::import_csv(&exported[..]).expect("valid CSV");
+    println!("Exported and imported back {} users", report.users.len());
+    let old_export = r#"{"version": 1, "users": [
+        {"username": "bob", "email": "bob@example.com", "active": true, "login_count": 3},
+        {"username": "1bob", "email": "bob@example.com", "active": true, "login_count": 3},
+        {"username": "eve", "email": "eve@example.com"}
+    ]}"#;
+    let report = transfer::import_json(old_export.as_bytes()).expect("valid JSON");
+    println!("Imported {} users", report.users.len());
+    for err in &report.errors {
+        println!("Error: {}", err);
+    }
+
     // Create tuple struct instance:
-    let pt = Point(0, 0);
-    println!("{}, {}", pt.0, pt.1);
+    let black = Color(0, 0, 0);
+    println!("{}, {}, {}", black.0, black.1, black.2);
+
+    // Create generic struct instance:
+    let pt = Point::new(0, 0);
+    println!("{}, {}", pt.x, pt.y);
     let pt2 = move_point(&pt, 5, 5);
-    println!("{}, {}", pt2.0, pt2.1);
+    println!("{}, {}, distance: {:.2}", pt2.x, pt2.y, pt.distance_to(pt2));
 
     // Create a regular tuple:
     let pt3 = (10, 10);
@@ -97,10 +137,18 @@ fn main() {
     // Print with {:?} works because we have #[derive(Debug)] for MyUnit
     println!("My Unit: {:?}", unit);
 
-    // Create 'newtype' structure instance:
-    let length = Inches(10);
-    // Extract the inner integer type through a destructuring let,
+    // Create 'newtype' structure instance, the units are defined in
+    // `src/units.rs`:
+    let length = Inches(10.0);
+    // Extract the inner value through a destructuring let,
     // just as with regular tuples:
-    let Inches(integer_length) = length;
-    println!("length is {} inches", integer_length);
+    let Inches(inner_length) = length;
+    println!("length is {} inches", inner_length);
+
+    // Values with the same unit can be added, other units have to be
+    // converted first, `length + Centimeters(5.0)` doesn't compile:
+    let total = length + Inches::from(Centimeters(5.08)) + Inches::from(Feet(1.0)) * 2.0;
+    println!("total length: {}, {:.1}", total, Centimeters::from(total));
+    println!("mass: {:.3}", Pounds::from(Kilograms(2.5)));
+    println!("time: {}", Seconds::from(Minutes(1.5)) - Seconds(30.0));
 }
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde_json;
+
+use user::{User, UserError};
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    NotFound(String),
+    AlreadyExists(String),
+    Invalid(UserError),
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RepositoryError::NotFound(ref username) => write!(f, "user '{}' not found", username),
+            RepositoryError::AlreadyExists(ref username) => write!(f, "user '{}' already exists", username),
+            RepositoryError::Invalid(ref err) => write!(f, "{}", err),
+            RepositoryError::Io(ref err) => write!(f, "storage error: {}", err),
+            RepositoryError::Json(ref err) => write!(f, "invalid storage data: {}", err),
+        }
+    }
+}
+
+impl error::Error for RepositoryError {}
+
+// The `From` implementations allow to use `?` with functions returning
+// other error types.
+impl From<UserError> for RepositoryError {
+    fn from(err: UserError) -> RepositoryError {
+        RepositoryError::Invalid(err)
+    }
+}
+
+impl From<io::Error> for RepositoryError {
+    fn from(err: io::Error) -> RepositoryError {
+        RepositoryError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for RepositoryError {
+    fn from(err: serde_json::Error) -> RepositoryError {
+        RepositoryError::Json(err)
+    }
+}
+
+// User storage, users are identified by the username.
+pub trait UserRepository {
+    fn create(&mut self, user: User) -> Result<(), RepositoryError>;
+    fn get(&self, username: &str) -> Result<User, RepositoryError>;
+    // Replace the user with the same username.
+    fn update(&mut self, user: User) -> Result<(), RepositoryError>;
+    fn delete(&mut self, username: &str) -> Result<User, RepositoryError>;
+    // All users sorted by username.
+    fn list(&self) -> Result<Vec<User>, RepositoryError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRepository {
+    // `BTreeMap` keeps the keys sorted, so `list` returns sorted users.
+    users: BTreeMap<String, User>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> InMemoryRepository {
+        InMemoryRepository::default()
+    }
+}
+
+impl UserRepository for InMemoryRepository {
+    fn create(&mut self, user: User) -> Result<(), RepositoryError> {
+        user.validate()?;
+        if self.users.contains_key(&user.username) {
+            return Err(RepositoryError::AlreadyExists(user.username));
+        }
+        self.users.insert(user.username.clone(), user);
+        Ok(())
+    }
+
+    fn get(&self, username: &str) -> Result<User, RepositoryError> {
+        self.users.get(username).cloned().ok_or_else(|| RepositoryError::NotFound(username.to_string()))
+    }
+
+    fn update(&mut self, user: User) -> Result<(), RepositoryError> {
+        user.validate()?;
+        match self.users.get_mut(&user.username) {
+            Some(existing) => {
+                *existing = user;
+                Ok(())
+            }
+            None => Err(RepositoryError::NotFound(user.username)),
+        }
+    }
+
+    fn delete(&mut self, username: &str) -> Result<User, RepositoryError> {
+        self.users.remove(username).ok_or_else(|| RepositoryError::NotFound(username.to_string()))
+    }
+
+    fn list(&self) -> Result<Vec<User>, RepositoryError> {
+        Ok(self.users.values().cloned().collect())
+    }
+}
+
+// Users stored in a JSON file.
+// The users are kept in memory and the file is rewritten after each
+// change. The change is made on a copy of the users and the copy
+// replaces the users in memory only after it is saved, so memory and
+// the file stay the same if saving fails.
+#[derive(Debug)]
+pub struct JsonFileRepository {
+    path: PathBuf,
+    memory: InMemoryRepository,
+}
+
+impl JsonFileRepository {
+    // Open the repository, the file is created on the first change if
+    // it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<JsonFileRepository, RepositoryError> {
+        let path = path.as_ref().to_path_buf();
+        let mut memory = InMemoryRepository::new();
+        match File::open(&path) {
+            Ok(file) => {
+                let users: Vec<User> = serde_json::from_reader(file)?;
+                for user in users {
+                    memory.create(user)?;
+                }
+            }
+            Err(ref err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(JsonFileRepository { path, memory })
+    }
+
+    // Apply the change to a copy of the users and save it.
+    fn change<T, F>(&mut self, change: F) -> Result<T, RepositoryError>
+    where
+        F: FnOnce(&mut InMemoryRepository) -> Result<T, RepositoryError>,
+    {
+        let mut memory = self.memory.clone();
+        let result = change(&mut memory)?;
+        self.save(&memory)?;
+        self.memory = memory;
+        Ok(result)
+    }
+
+    // Write to a temporary file first and then rename it, so we don't
+    // end up with a half-written file if something goes wrong.
+    fn save(&self, memory: &InMemoryRepository) -> Result<(), RepositoryError> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        let file = File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(file, &memory.list()?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl UserRepository for JsonFileRepository {
+    fn create(&mut self, user: User) -> Result<(), RepositoryError> {
+        self.change(|memory| memory.create(user))
+    }
+
+    fn get(&self, username: &str) -> Result<User, RepositoryError> {
+        self.memory.get(username)
+    }
+
+    fn update(&mut self, user: User) -> Result<(), RepositoryError> {
+        self.change(|memory| memory.update(user))
+    }
+
+    fn delete(&mut self, username: &str) -> Result<User, RepositoryError> {
+        self.change(|memory| memory.delete(username))
+    }
+
+    fn list(&self) -> Result<Vec<User>, RepositoryError> {
+        self.memory.list()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use user::build_user;
+
+    fn user(username: &str) -> User {
+        build_user(username.to_string(), format!("{}@example.com", username)).unwrap()
+    }
+
+    // A path in the temporary directory, unique for the test.
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("structs-{}-{}.json", name, process::id()))
+    }
+
+    #[test]
+    fn in_memory_crud() {
+        let mut repo = InMemoryRepository::new();
+        repo.create(user("bob")).unwrap();
+        repo.create(user("ann")).unwrap();
+        match repo.create(user("ann")) {
+            Err(RepositoryError::AlreadyExists(ref name)) if name == "ann" => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut ann = repo.get("ann").unwrap();
+        ann.login_count = 5;
+        repo.update(ann).unwrap();
+        assert_eq!(repo.get("ann").unwrap().login_count, 5);
+
+        let names: Vec<String> = repo.list().unwrap().into_iter().map(|user| user.username).collect();
+        assert_eq!(names, vec!["ann", "bob"]);
+
+        assert_eq!(repo.delete("bob").unwrap().username, "bob");
+        match repo.get("bob") {
+            Err(RepositoryError::NotFound(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match repo.update(user("bob")) {
+            Err(RepositoryError::NotFound(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validates_users() {
+        let mut repo = InMemoryRepository::new();
+        let mut invalid = user("ann");
+        invalid.email = String::from("not an email");
+        match repo.create(invalid.clone()) {
+            Err(RepositoryError::Invalid(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        repo.create(user("ann")).unwrap();
+        match repo.update(invalid) {
+            Err(RepositoryError::Invalid(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn json_file_round_trip() {
+        let path = temp_path("round-trip");
+        let _ = fs::remove_file(&path);
+        {
+            let mut repo = JsonFileRepository::open(&path).unwrap();
+            repo.create(user("ann")).unwrap();
+            repo.create(user("bob")).unwrap();
+            repo.delete("bob").unwrap();
+        }
+        let repo = JsonFileRepository::open(&path).unwrap();
+        assert_eq!(repo.list().unwrap(), vec![user("ann")]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_save_keeps_memory_unchanged() {
+        // The directory doesn't exist, so saving fails.
+        let path = temp_path("missing-dir").join("users.json");
+        let mut repo = JsonFileRepository::open(&path).unwrap();
+        for _ in 0..2 {
+            match repo.create(user("ann")) {
+                Err(RepositoryError::Io(_)) => {}
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert!(repo.list().unwrap().is_empty());
+    }
+}
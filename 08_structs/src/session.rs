@@ -0,0 +1,276 @@
+// Login sessions: a table of session tokens with expiry time.
+//
+// The current time comes from the `Clock` trait, so the expiry can be
+// tested without waiting: use the `ManualClock` and move the time
+// forward with `advance`.
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use getrandom;
+
+use credentials::CredentialError;
+use user::User;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    // The user is deactivated and can not log in.
+    Inactive(String),
+    NotLoggedIn(String),
+    Credentials(CredentialError),
+    InvalidToken,
+    Expired,
+    // The OS random generator failed, the token can not be created.
+    Random(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SessionError::Inactive(ref username) => write!(f, "user '{}' is not active", username),
+            SessionError::NotLoggedIn(ref username) => write!(f, "user '{}' is not logged in", username),
+            SessionError::Credentials(ref err) => write!(f, "login failed: {}", err),
+            SessionError::InvalidToken => write!(f, "invalid session token"),
+            SessionError::Expired => write!(f, "session expired"),
+            SessionError::Random(ref err) => write!(f, "failed to generate session token: {}", err),
+        }
+    }
+}
+
+impl error::Error for SessionError {}
+
+impl From<CredentialError> for SessionError {
+    fn from(err: CredentialError) -> SessionError {
+        SessionError::Credentials(err)
+    }
+}
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+// The real time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// The time that only changes when we change it.
+// `Cell` allows to change the time through a shared reference (`&self`),
+// this is called "interior mutability".
+pub struct ManualClock {
+    now: Cell<SystemTime>,
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> ManualClock {
+        ManualClock { now: Cell::new(now) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
+
+// Implementing the trait for references allows to keep the clock outside
+// of the `SessionManager` (to advance it while the manager uses it).
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub token: String,
+    pub username: String,
+    pub expires_at: SystemTime,
+}
+
+pub struct SessionManager<C: Clock> {
+    clock: C,
+    // How long the session is valid.
+    ttl: Duration,
+    sessions: HashMap<String, Session>,
+}
+
+impl<C: Clock> SessionManager<C> {
+    pub fn new(clock: C, ttl: Duration) -> SessionManager<C> {
+        SessionManager { clock, ttl, sessions: HashMap::new() }
+    }
+
+    // Hard to guess token: 128 random bits from the OS random source,
+    // as hex.
+    fn new_token() -> Result<String, SessionError> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).map_err(|err| SessionError::Random(err.to_string()))?;
+        Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    // Log the user in and start a new session, returns the session token.
+    pub fn login(&mut self, user: &mut User) -> Result<String, SessionError> {
+        let now = self.clock.now();
+        // Create the token first, so the user isn't marked as logged in
+        // if it fails.
+        let token = Self::new_token()?;
+        user.login(now)?;
+        let session = Session { token: token.clone(), username: user.username.clone(), expires_at: now + self.ttl };
+        self.sessions.insert(token.clone(), session);
+        Ok(token)
+    }
+
+    // Check the password and log the user in.
+    // If the password hash is outdated, it is replaced with a new one
+    // (the password is only known at this point).
+    pub fn login_with_password(&mut self, user: &mut User, password: &str) -> Result<String, SessionError> {
+        user.verify_password(password)?;
+        let outdated = user.password_hash.as_ref().is_some_and(|hash| hash.needs_rehash());
+        if outdated {
+            user.set_password(password)?;
+        }
+        self.login(user)
+    }
+
+    // Find the session by token, expired sessions are not valid.
+    pub fn validate(&self, token: &str) -> Result<&Session, SessionError> {
+        let session = self.sessions.get(token).ok_or(SessionError::InvalidToken)?;
+        if session.expires_at <= self.clock.now() {
+            return Err(SessionError::Expired);
+        }
+        Ok(session)
+    }
+
+    // End the session, the user is logged out when the last session ends.
+    pub fn logout(&mut self, token: &str, user: &mut User) -> Result<(), SessionError> {
+        match self.sessions.get(token) {
+            Some(session) if session.username == user.username => {}
+            _ => return Err(SessionError::InvalidToken),
+        }
+        self.sessions.remove(token);
+        if self.user_sessions(&user.username).is_empty() {
+            user.logout()?;
+        }
+        Ok(())
+    }
+
+    // Deactivate the user and end all user sessions.
+    pub fn deactivate(&mut self, user: &mut User) {
+        user.deactivate();
+        let username = user.username.clone();
+        self.sessions.retain(|_, session| session.username != username);
+    }
+
+    pub fn user_sessions(&self, username: &str) -> Vec<&Session> {
+        self.sessions.values().filter(|session| session.username == username).collect()
+    }
+
+    // Remove expired sessions, returns the number of removed sessions.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let before = self.sessions.len();
+        self.sessions.retain(|_, session| session.expires_at > now);
+        before - self.sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use user::build_user;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn user(username: &str) -> User {
+        build_user(username.to_string(), format!("{}@example.com", username)).unwrap()
+    }
+
+    fn clock() -> ManualClock {
+        ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000))
+    }
+
+    #[test]
+    fn session_expires() {
+        let clock = clock();
+        let mut sessions = SessionManager::new(&clock, TTL);
+        let mut ann = user("ann");
+        let token = sessions.login(&mut ann).unwrap();
+        assert_eq!(sessions.validate(&token).unwrap().username, "ann");
+
+        clock.advance(TTL - Duration::from_secs(1));
+        assert!(sessions.validate(&token).is_ok());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(sessions.validate(&token), Err(SessionError::Expired));
+        assert_eq!(sessions.validate("unknown"), Err(SessionError::InvalidToken));
+    }
+
+    #[test]
+    fn purge_removes_only_expired_sessions() {
+        let clock = clock();
+        let mut sessions = SessionManager::new(&clock, TTL);
+        let mut ann = user("ann");
+        let mut bob = user("bob");
+        let old = sessions.login(&mut ann).unwrap();
+        clock.advance(Duration::from_secs(30));
+        let new = sessions.login(&mut bob).unwrap();
+        clock.advance(Duration::from_secs(30));
+
+        assert_eq!(sessions.purge_expired(), 1);
+        assert_eq!(sessions.validate(&old), Err(SessionError::InvalidToken));
+        assert!(sessions.validate(&new).is_ok());
+        assert_eq!(sessions.purge_expired(), 0);
+    }
+
+    #[test]
+    fn logout_of_last_session_logs_user_out() {
+        let clock = clock();
+        let mut sessions = SessionManager::new(&clock, TTL);
+        let mut ann = user("ann");
+        let first = sessions.login(&mut ann).unwrap();
+        let second = sessions.login(&mut ann).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.len(), 32);
+        assert_eq!(ann.login_count, 2);
+
+        sessions.logout(&first, &mut ann).unwrap();
+        assert!(ann.logged_in);
+        sessions.logout(&second, &mut ann).unwrap();
+        assert!(!ann.logged_in);
+        assert_eq!(sessions.logout(&second, &mut ann), Err(SessionError::InvalidToken));
+    }
+
+    #[test]
+    fn logout_with_other_user_token_fails() {
+        let clock = clock();
+        let mut sessions = SessionManager::new(&clock, TTL);
+        let mut ann = user("ann");
+        let mut bob = user("bob");
+        let token = sessions.login(&mut ann).unwrap();
+        assert_eq!(sessions.logout(&token, &mut bob), Err(SessionError::InvalidToken));
+        assert!(sessions.validate(&token).is_ok());
+    }
+
+    #[test]
+    fn inactive_user_can_not_log_in() {
+        let clock = clock();
+        let mut sessions = SessionManager::new(&clock, TTL);
+        let mut ann = user("ann");
+        let token = sessions.login(&mut ann).unwrap();
+
+        sessions.deactivate(&mut ann);
+        assert_eq!(sessions.validate(&token), Err(SessionError::InvalidToken));
+        assert!(sessions.user_sessions("ann").is_empty());
+        assert_eq!(sessions.login(&mut ann), Err(SessionError::Inactive(String::from("ann"))));
+        assert_eq!(ann.login_count, 1);
+    }
+}
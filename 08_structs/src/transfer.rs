@@ -0,0 +1,353 @@
+// Bulk import and export of users in JSON and CSV.
+//
+// Exported files carry the schema version, so older exports can still
+// be imported after new fields are added to `User`:
+//
+// - version 1: username, email, active, login_count
+// - version 2: + logged_in, last_login (unix time), password_hash
+//
+// `last_login` is stored in whole seconds, the fraction of the second is
+// dropped on export.
+//
+// Password hashes are exported only with `Credentials::Include`, by
+// default the `password_hash` field is left empty so an export can be
+// shared without the credentials.
+//
+// JSON: `{"version": 2, "users": [{"username": ..., ...}, ...]}`.
+// CSV: the first line is `# version: 2`, then the header and the rows.
+//
+// Import doesn't stop on the first bad row: valid users are returned
+// together with the list of errors for invalid rows.
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+use csv;
+use serde_json::{self, Value};
+
+use credentials::{CredentialError, PasswordHash};
+use user::User;
+
+pub const CURRENT_VERSION: u32 = 2;
+
+// Whether the export contains the password hashes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Credentials {
+    Omit,
+    Include,
+}
+
+// Errors that prevent reading the file at all.
+#[derive(Debug)]
+pub enum TransferError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    MissingVersion,
+    // The file was exported by a newer version of the program (or the
+    // version is not valid, like 0).
+    UnsupportedVersion(u64),
+    // The JSON document is not `{"version": .., "users": [..]}`.
+    InvalidDocument(&'static str),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransferError::Io(ref err) => write!(f, "failed to read or write users: {}", err),
+            TransferError::Json(ref err) => write!(f, "invalid JSON: {}", err),
+            TransferError::Csv(ref err) => write!(f, "invalid CSV: {}", err),
+            TransferError::MissingVersion => write!(f, "schema version is missing"),
+            TransferError::UnsupportedVersion(version) => write!(
+                f, "unsupported schema version {} (supported 1 to {})", version, CURRENT_VERSION),
+            TransferError::InvalidDocument(reason) => write!(f, "invalid document: {}", reason),
+        }
+    }
+}
+
+impl error::Error for TransferError {}
+
+impl From<io::Error> for TransferError {
+    fn from(err: io::Error) -> TransferError {
+        TransferError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for TransferError {
+    fn from(err: serde_json::Error) -> TransferError {
+        TransferError::Json(err)
+    }
+}
+
+impl From<csv::Error> for TransferError {
+    fn from(err: csv::Error) -> TransferError {
+        TransferError::Csv(err)
+    }
+}
+
+// Error in a single row, `row` starts from 1 (for CSV it is the data row
+// number, not counting the version line and the header).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub users: Vec<User>,
+    pub errors: Vec<RowError>,
+}
+
+// User as it is stored in files: flat structure with simple types, so
+// the same record works for JSON and CSV.
+// Fields missing in older versions get default values.
+#[derive(Debug, Serialize, Deserialize)]
+struct UserRecord {
+    username: String,
+    email: String,
+    active: bool,
+    login_count: u64,
+    #[serde(default)]
+    logged_in: bool,
+    #[serde(default)]
+    last_login: Option<u64>,
+    #[serde(default)]
+    password_hash: Option<String>,
+}
+
+impl UserRecord {
+    fn new(user: &User, credentials: Credentials) -> UserRecord {
+        UserRecord {
+            username: user.username.clone(),
+            email: user.email.clone(),
+            active: user.active,
+            login_count: user.login_count,
+            logged_in: user.logged_in,
+            last_login: user.last_login
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs()),
+            password_hash: match credentials {
+                Credentials::Include => user.password_hash.as_ref().map(|hash| hash.to_string()),
+                Credentials::Omit => None,
+            },
+        }
+    }
+
+    fn into_user(self, version: u32) -> Result<User, String> {
+        let mut user = User {
+            username: self.username,
+            email: self.email,
+            active: self.active,
+            login_count: self.login_count,
+            logged_in: self.logged_in,
+            last_login: self.last_login.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            password_hash: match self.password_hash {
+                Some(ref hash) if !hash.is_empty() => Some(parse_hash(hash)?),
+                _ => None,
+            },
+        };
+        // Version 1 didn't have the session and password fields, make
+        // sure they have the default values.
+        if version < 2 {
+            user.logged_in = false;
+            user.last_login = None;
+            user.password_hash = None;
+        }
+        user.validate().map_err(|err| err.to_string())?;
+        Ok(user)
+    }
+}
+
+fn parse_hash(hash: &str) -> Result<PasswordHash, String> {
+    hash.parse().map_err(|err: CredentialError| err.to_string())
+}
+
+fn check_version(version: u64) -> Result<u32, TransferError> {
+    match u32::try_from(version) {
+        Ok(version) if (1..=CURRENT_VERSION).contains(&version) => Ok(version),
+        _ => Err(TransferError::UnsupportedVersion(version)),
+    }
+}
+
+pub fn export_json<W: Write>(users: &[User], credentials: Credentials, writer: W) -> Result<(), TransferError> {
+    let records: Vec<UserRecord> = users.iter().map(|user| UserRecord::new(user, credentials)).collect();
+    let document = json!({ "version": CURRENT_VERSION, "users": records });
+    serde_json::to_writer_pretty(writer, &document)?;
+    Ok(())
+}
+
+pub fn import_json<R: Read>(reader: R) -> Result<ImportReport, TransferError> {
+    let mut document: Value = serde_json::from_reader(reader)?;
+    let version = document.get("version").and_then(Value::as_u64).ok_or(TransferError::MissingVersion)?;
+    let version = check_version(version)?;
+
+    let mut report = ImportReport::default();
+    // `take` moves the rows out of the document instead of copying them.
+    let rows = match document.get_mut("users").map(Value::take) {
+        Some(Value::Array(rows)) => rows,
+        Some(_) => return Err(TransferError::InvalidDocument("`users` should be an array")),
+        None => return Err(TransferError::InvalidDocument("`users` is missing")),
+    };
+    // Each row is converted separately, so one bad row doesn't stop the
+    // import.
+    for (index, row) in rows.into_iter().enumerate() {
+        let user = serde_json::from_value::<UserRecord>(row)
+            .map_err(|err| err.to_string())
+            .and_then(|record| record.into_user(version));
+        match user {
+            Ok(user) => report.users.push(user),
+            Err(message) => report.errors.push(RowError { row: index + 1, message }),
+        }
+    }
+    Ok(report)
+}
+
+pub fn export_csv<W: Write>(users: &[User], credentials: Credentials, mut writer: W) -> Result<(), TransferError> {
+    writeln!(writer, "# version: {}", CURRENT_VERSION)?;
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for user in users {
+        csv_writer.serialize(UserRecord::new(user, credentials))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+pub fn import_csv<R: Read>(reader: R) -> Result<ImportReport, TransferError> {
+    let mut reader = BufReader::new(reader);
+    // The first line is the version: `# version: 2`.
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+    let version = first_line.trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_start_matches("version:")
+        .trim()
+        .parse()
+        .map_err(|_| TransferError::MissingVersion)?;
+    let version = check_version(version)?;
+
+    let mut report = ImportReport::default();
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    for (index, record) in csv_reader.deserialize::<UserRecord>().enumerate() {
+        let user = record
+            .map_err(|err| err.to_string())
+            .and_then(|record| record.into_user(version));
+        match user {
+            Ok(user) => report.users.push(user),
+            Err(message) => report.errors.push(RowError { row: index + 1, message }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use credentials::PasswordHash;
+    use user::build_user;
+
+    fn users() -> Vec<User> {
+        let mut ann = build_user(String::from("ann"), String::from("ann@example.com")).unwrap();
+        ann.login_count = 3;
+        ann.last_login = Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        ann.password_hash = Some(PasswordHash::with_iterations("correct horse", 1_000).unwrap());
+        let mut bob = build_user(String::from("bob"), String::from("bob@example.com")).unwrap();
+        bob.active = false;
+        vec![ann, bob]
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut json = Vec::new();
+        let users = users();
+        export_json(&users, Credentials::Include, &mut json).unwrap();
+        let report = import_json(&json[..]).unwrap();
+        assert_eq!(report.users, users);
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut csv = Vec::new();
+        let users = users();
+        export_csv(&users, Credentials::Include, &mut csv).unwrap();
+        assert!(csv.starts_with(b"# version: 2\n"));
+        let report = import_csv(&csv[..]).unwrap();
+        assert_eq!(report.users, users);
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn omits_credentials_by_choice() {
+        let users = users();
+        let stored = users[0].password_hash.as_ref().unwrap().to_string();
+        let mut csv = Vec::new();
+        export_csv(&users, Credentials::Omit, &mut csv).unwrap();
+        let mut json = Vec::new();
+        export_json(&users, Credentials::Omit, &mut json).unwrap();
+        for exported in &[&csv, &json] {
+            assert!(!String::from_utf8_lossy(exported).contains(&stored));
+        }
+
+        let report = import_json(&json[..]).unwrap();
+        assert_eq!(report.users[0].password_hash, None);
+        assert_eq!(report.users[0].username, "ann");
+    }
+
+    #[test]
+    fn last_login_is_truncated_to_seconds() {
+        let mut users = users();
+        users[0].last_login = Some(UNIX_EPOCH + Duration::from_millis(1_700_000_000_999));
+        let mut json = Vec::new();
+        export_json(&users, Credentials::Omit, &mut json).unwrap();
+        let report = import_json(&json[..]).unwrap();
+        assert_eq!(report.users[0].last_login, Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+    }
+
+    #[test]
+    fn imports_version_1_and_reports_bad_rows() {
+        let csv = "# version: 1\nusername,email,active,login_count\nann,ann@example.com,true,2\n1x,x@example.com,true,0\n";
+        let report = import_csv(csv.as_bytes()).unwrap();
+        assert_eq!(report.users.len(), 1);
+        assert_eq!(report.users[0].login_count, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row, 2);
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for json in &[r#"{"version": 0, "users": []}"#, r#"{"version": 3, "users": []}"#, r#"{"version": 4294967297, "users": []}"#] {
+            match import_json(json.as_bytes()) {
+                Err(TransferError::UnsupportedVersion(_)) => {}
+                other => panic!("unexpected result for {}: {:?}", json, other),
+            }
+        }
+        match import_csv("# version: 4294967297\nusername\n".as_bytes()) {
+            Err(TransferError::UnsupportedVersion(4_294_967_297)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match import_json(r#"{"users": []}"#.as_bytes()) {
+            Err(TransferError::MissingVersion) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for json in &[r#"{"version": 2}"#, r#"{"version": 2, "users": {}}"#] {
+            match import_json(json.as_bytes()) {
+                Err(TransferError::InvalidDocument(_)) => {}
+                other => panic!("unexpected result for {}: {:?}", json, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Units of measure built with the 'newtype' pattern.
+//!
+//! There is one case when a tuple struct is very useful, and that's a
+//! tuple struct with only one element. We call this the 'newtype' pattern,
+//! because it allows you to create a new type, distinct from that of its
+//! contained value and expressing its own semantic meaning.
+//!
+//! Each unit is a separate type, so the compiler checks that we only
+//! combine compatible values:
+//!
+//! ```
+//! use structs::units::{Centimeters, Inches};
+//!
+//! let total = Inches(10.0) + Inches(2.0);
+//! assert_eq!(total, Inches(12.0));
+//! let total = Inches(10.0) + Inches::from(Centimeters(2.54));
+//! assert_eq!(total, Inches(11.0));
+//! ```
+//!
+//! Adding centimetres to inches without a conversion doesn't compile:
+//!
+//! ```compile_fail,E0308
+//! use structs::units::{Centimeters, Inches};
+//!
+//! let total = Inches(10.0) + Centimeters(2.0);
+//! ```
+//!
+//! Values can be added and subtracted only with the same unit and
+//! multiplied or divided by a scalar (`f64`). Conversions between the
+//! units of the same quantity (length, mass or time) are explicit, with
+//! `From` / `Into`.
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+// Define the unit type with arithmetic operators and `Display` which
+// shows the value with the unit suffix, like `10 in`.
+macro_rules! unit {
+    ($(#[$attr:meta])* $name:ident, $suffix:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name(pub f64);
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, other: $name) -> $name {
+                $name(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 - other.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+
+            fn mul(self, scalar: f64) -> $name {
+                $name(self.0 * scalar)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, value: $name) -> $name {
+                $name(self * value.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+
+            fn div(self, scalar: f64) -> $name {
+                $name(self.0 / scalar)
+            }
+        }
+
+        impl fmt::Display for $name {
+            // The precision is passed to the number, so `{:.2}` shows
+            // `2.54 cm`.
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match f.precision() {
+                    Some(precision) => write!(f, "{:.*} {}", precision, self.0, $suffix),
+                    None => write!(f, "{} {}", self.0, $suffix),
+                }
+            }
+        }
+    };
+}
+
+// Conversions in both directions between two units of the same quantity,
+// `$factor` is how many `$small` units are in one `$large` unit.
+macro_rules! convert {
+    ($large:ident = $factor:expr, $small:ident) => {
+        impl From<$large> for $small {
+            fn from(value: $large) -> $small {
+                $small(value.0 * $factor)
+            }
+        }
+
+        impl From<$small> for $large {
+            fn from(value: $small) -> $large {
+                $large(value.0 / $factor)
+            }
+        }
+    };
+}
+
+// Length.
+unit!(Inches, "in");
+unit!(Feet, "ft");
+unit!(Centimeters, "cm");
+unit!(Meters, "m");
+
+convert!(Feet = 12.0, Inches);
+convert!(Inches = 2.54, Centimeters);
+convert!(Feet = 30.48, Centimeters);
+convert!(Meters = 100.0, Centimeters);
+convert!(Meters = 1.0 / 0.0254, Inches);
+convert!(Meters = 1.0 / 0.3048, Feet);
+
+// Mass.
+unit!(Grams, "g");
+unit!(Kilograms, "kg");
+unit!(Pounds, "lb");
+
+convert!(Kilograms = 1000.0, Grams);
+convert!(Pounds = 453.592_37, Grams);
+convert!(Kilograms = 1.0 / 0.453_592_37, Pounds);
+
+// Time.
+unit!(Seconds, "s");
+unit!(Minutes, "min");
+unit!(Hours, "h");
+
+convert!(Minutes = 60.0, Seconds);
+convert!(Hours = 3600.0, Seconds);
+convert!(Hours = 60.0, Minutes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Converting there and back returns the original value (up to the
+    // floating point error).
+    macro_rules! assert_round_trip {
+        ($from:ident, $to:ident) => {
+            for &value in &[0.0, 1.0, 2.5, -7.0, 1234.5678] {
+                let back = $from::from($to::from($from(value)));
+                assert!((back.0 - value).abs() < 1e-9, "{} -> {} -> {}", $from(value), $to::from($from(value)), back);
+            }
+        };
+    }
+
+    #[test]
+    fn conversion_factors() {
+        assert_eq!(Inches::from(Feet(1.0)), Inches(12.0));
+        assert_eq!(Centimeters::from(Inches(1.0)), Centimeters(2.54));
+        assert_eq!(Centimeters::from(Meters(1.0)), Centimeters(100.0));
+        assert!((Feet::from(Meters(1.0)).0 - 3.280_839_9).abs() < 1e-6);
+        assert_eq!(Grams::from(Kilograms(1.0)), Grams(1000.0));
+        assert_eq!(Grams::from(Pounds(1.0)), Grams(453.592_37));
+        assert_eq!(Seconds::from(Hours(1.0)), Seconds(3600.0));
+        assert_eq!(Minutes::from(Hours(1.5)), Minutes(90.0));
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        assert_round_trip!(Feet, Inches);
+        assert_round_trip!(Inches, Centimeters);
+        assert_round_trip!(Feet, Centimeters);
+        assert_round_trip!(Meters, Centimeters);
+        assert_round_trip!(Meters, Inches);
+        assert_round_trip!(Meters, Feet);
+        assert_round_trip!(Kilograms, Grams);
+        assert_round_trip!(Pounds, Grams);
+        assert_round_trip!(Kilograms, Pounds);
+        assert_round_trip!(Minutes, Seconds);
+        assert_round_trip!(Hours, Seconds);
+        assert_round_trip!(Hours, Minutes);
+    }
+
+    #[test]
+    fn conversions_agree() {
+        // Feet -> inches -> centimetres is the same as feet -> centimetres.
+        let direct = Centimeters::from(Feet(3.0));
+        let via_inches = Centimeters::from(Inches::from(Feet(3.0)));
+        assert!((direct.0 - via_inches.0).abs() < 1e-9);
+        let direct = Pounds::from(Kilograms(2.0));
+        let via_grams = Pounds::from(Grams::from(Kilograms(2.0)));
+        assert!((direct.0 - via_grams.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn arithmetic_and_display() {
+        assert_eq!(Inches(10.0) - Inches(4.0), Inches(6.0));
+        assert_eq!(Inches(10.0) * 2.0, 2.0 * Inches(10.0));
+        assert_eq!(Seconds(60.0) / 4.0, Seconds(15.0));
+        assert_eq!(format!("{}", Inches(10.0)), "10 in");
+        assert_eq!(format!("{:.1}", Centimeters::from(Inches(36.0))), "91.4 cm");
+    }
+}
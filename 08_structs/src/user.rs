@@ -0,0 +1,264 @@
+use std::error;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use credentials::{CredentialError, PasswordHash};
+use session::SessionError;
+
+// Structure is a collection of fields of various types.
+// The #[derive(Debug)] is to be able to print the struct.
+// The `Serialize` and `Deserialize` are needed to save users to a file.
+// The `#[serde(default)]` fields were added later, they get default
+// values when loading older files that don't have them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub username: String,
+    pub email: String,
+    pub active: bool,
+    pub login_count: u64,
+    #[serde(default)]
+    pub logged_in: bool,
+    #[serde(default)]
+    pub last_login: Option<SystemTime>,
+    // The `PasswordHash` hides the hash in the `Debug` output, so it is
+    // safe to print the user with `{:?}`.
+    #[serde(default)]
+    pub password_hash: Option<PasswordHash>,
+}
+
+// Define a method of the structure.
+impl User {
+    pub fn display_name(&self) -> String {
+        self.username.clone()
+    }
+
+    pub fn display_status(&self) -> String {
+        let active = if self.active { "active" } else { "inactive" };
+        let logged_in = if self.logged_in { "logged in" } else { "logged out" };
+        // Without extra crates (like `chrono`) we can only show the time
+        // as the number of seconds since 1970-01-01 (the unix time).
+        let last_login = match self.last_login.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+            Some(since_epoch) => format!("{} (unix time)", since_epoch.as_secs()),
+            None => String::from("never"),
+        };
+        format!("Status: {}, {}, login count: {}, last login: {}",
+                active, logged_in, self.login_count, last_login)
+    }
+
+    // Inactive users can not log in.
+    pub fn login(&mut self, now: SystemTime) -> Result<(), SessionError> {
+        if !self.active {
+            return Err(SessionError::Inactive(self.username.clone()));
+        }
+        self.login_count += 1;
+        self.last_login = Some(now);
+        self.logged_in = true;
+        Ok(())
+    }
+
+    pub fn logout(&mut self) -> Result<(), SessionError> {
+        if !self.logged_in {
+            return Err(SessionError::NotLoggedIn(self.username.clone()));
+        }
+        self.logged_in = false;
+        Ok(())
+    }
+
+    pub fn set_password(&mut self, password: &str) -> Result<(), CredentialError> {
+        self.password_hash = Some(PasswordHash::new(password)?);
+        Ok(())
+    }
+
+    pub fn verify_password(&self, password: &str) -> Result<(), CredentialError> {
+        match self.password_hash {
+            Some(ref hash) if hash.verify(password) => Ok(()),
+            Some(_) => Err(CredentialError::WrongPassword),
+            None => Err(CredentialError::NoPassword),
+        }
+    }
+
+    // Password rotation: the old password is required to set the new one.
+    pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<(), CredentialError> {
+        self.verify_password(old_password)?;
+        self.set_password(new_password)
+    }
+
+    pub fn deactivate(&mut self) {
+        self.active = false;
+        self.logged_in = false;
+    }
+
+    // Check the username and email.
+    pub fn validate(&self) -> Result<(), UserError> {
+        validate_username(&self.username)?;
+        validate_email(&self.email)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserError {
+    InvalidUsername { username: String, reason: &'static str },
+    InvalidEmail { email: String, reason: &'static str },
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserError::InvalidUsername { ref username, reason } => {
+                write!(f, "invalid username '{}': {}", username, reason)
+            }
+            UserError::InvalidEmail { ref email, reason } => write!(f, "invalid email '{}': {}", email, reason),
+        }
+    }
+}
+
+impl error::Error for UserError {}
+
+pub const USERNAME_MIN_LENGTH: usize = 3;
+pub const USERNAME_MAX_LENGTH: usize = 32;
+
+// Username is 3 to 32 characters long, starts with a letter and has
+// only letters, digits, `_`, `-` and `.`.
+pub fn validate_username(username: &str) -> Result<(), UserError> {
+    let invalid = |reason| Err(UserError::InvalidUsername { username: username.to_string(), reason });
+    if username.len() < USERNAME_MIN_LENGTH {
+        return invalid("too short, should be at least 3 characters");
+    }
+    if username.len() > USERNAME_MAX_LENGTH {
+        return invalid("too long, should be at most 32 characters");
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return invalid("should start with a letter");
+    }
+    if !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.') {
+        return invalid("only letters, digits, '_', '-' and '.' are allowed");
+    }
+    Ok(())
+}
+
+// A simple email check: `local@domain.tld` without spaces.
+// Note: the full email address syntax (RFC 5322) is much more complex,
+// the only reliable way to check the address is to send an email to it.
+pub fn validate_email(email: &str) -> Result<(), UserError> {
+    let invalid = |reason| Err(UserError::InvalidEmail { email: email.to_string(), reason });
+    if email.chars().any(char::is_whitespace) {
+        return invalid("should not contain spaces");
+    }
+    // `splitn(2, '@')` splits into the part before the first '@' and the
+    // rest.
+    let mut parts = email.splitn(2, '@');
+    let local = parts.next().unwrap_or("");
+    let domain = match parts.next() {
+        Some(domain) => domain,
+        None => return invalid("should contain '@'"),
+    };
+    if local.is_empty() {
+        return invalid("the part before '@' is empty");
+    }
+    if domain.contains('@') {
+        return invalid("should contain only one '@'");
+    }
+    if !domain.contains('.') || domain.split('.').any(|label| label.is_empty()) {
+        return invalid("the domain should look like 'example.com'");
+    }
+    Ok(())
+}
+
+// Constructor-like function with defaults.
+// It checks the username and email and returns an error if they are
+// not valid.
+#[allow(clippy::redundant_field_names)]
+pub fn build_user(username: String, email: String) -> Result<User, UserError> {
+    validate_username(&username)?;
+    validate_email(&email)?;
+    Ok(User {
+        username: username,
+        email,  // shorthand syntax, field name same as parameter name
+        active: true,
+        login_count: 0,
+        logged_in: false,
+        last_login: None,
+        password_hash: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The reason why the username is rejected, `None` if it is valid.
+    fn username_error(username: &str) -> Option<&'static str> {
+        match validate_username(username) {
+            Ok(()) => None,
+            Err(UserError::InvalidUsername { reason, .. }) => Some(reason),
+            Err(err) => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    fn email_error(email: &str) -> Option<&'static str> {
+        match validate_email(email) {
+            Ok(()) => None,
+            Err(UserError::InvalidEmail { reason, .. }) => Some(reason),
+            Err(err) => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn username_length_bounds() {
+        let too_short = Some("too short, should be at least 3 characters");
+        let too_long = Some("too long, should be at most 32 characters");
+        assert_eq!(username_error(""), too_short);
+        assert_eq!(username_error("ab"), too_short);
+        assert_eq!(username_error("abc"), None);
+        assert_eq!(username_error(&"a".repeat(USERNAME_MAX_LENGTH)), None);
+        assert_eq!(username_error(&"a".repeat(USERNAME_MAX_LENGTH + 1)), too_long);
+    }
+
+    #[test]
+    fn username_characters() {
+        assert_eq!(username_error("user_1.name-2"), None);
+        assert_eq!(username_error("Z99"), None);
+        let start = Some("should start with a letter");
+        assert_eq!(username_error("1user"), start);
+        assert_eq!(username_error("_user"), start);
+        assert_eq!(username_error(".user"), start);
+        let allowed = Some("only letters, digits, '_', '-' and '.' are allowed");
+        assert_eq!(username_error("user name"), allowed);
+        assert_eq!(username_error("user@host"), allowed);
+        assert_eq!(username_error("user/.."), allowed);
+        // Only ASCII letters, the length is counted in bytes.
+        assert_eq!(username_error("usér"), allowed);
+        assert_eq!(username_error("éa"), start);
+    }
+
+    #[test]
+    fn valid_emails() {
+        assert_eq!(email_error("someone@example.com"), None);
+        assert_eq!(email_error("first.last+tag@mail.example.co.uk"), None);
+        assert_eq!(email_error("a@b.c"), None);
+    }
+
+    #[test]
+    fn rejected_emails() {
+        assert_eq!(email_error("someone example.com"), Some("should not contain spaces"));
+        assert_eq!(email_error("someone@example.com\n"), Some("should not contain spaces"));
+        assert_eq!(email_error("someone.example.com"), Some("should contain '@'"));
+        assert_eq!(email_error(""), Some("should contain '@'"));
+        assert_eq!(email_error("@example.com"), Some("the part before '@' is empty"));
+        assert_eq!(email_error("a@b@example.com"), Some("should contain only one '@'"));
+        let domain = Some("the domain should look like 'example.com'");
+        assert_eq!(email_error("someone@"), domain);
+        assert_eq!(email_error("someone@localhost"), domain);
+        assert_eq!(email_error("someone@.com"), domain);
+        assert_eq!(email_error("someone@example."), domain);
+        assert_eq!(email_error("someone@example..com"), domain);
+    }
+
+    #[test]
+    fn build_user_validates() {
+        let user = build_user(String::from("alice"), String::from("alice@example.com")).unwrap();
+        assert!(user.active);
+        assert!(build_user(String::from("al"), String::from("alice@example.com")).is_err());
+        assert!(build_user(String::from("alice"), String::from("alice")).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// Compare the quadtree with the naive linear scan.
+//
+// Run with `cargo bench`, criterion prints the time for each
+// benchmark and saves the HTML report into `target/criterion`.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use structs_rectangle::spatial::{self, QuadTree};
+use structs_rectangle::Rect;
+
+const WORLD: i32 = 10_000;
+
+// Simple pseudo-random generator (linear congruential), so the
+// benchmark data is the same on each run without extra dependencies.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, max: i32) -> i32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % max as u64) as i32
+    }
+}
+
+fn random_rects(count: usize) -> Vec<Rect<i32>> {
+    let mut rng = Lcg(42);
+    (0..count)
+        .map(|_| {
+            let (x, y) = (rng.next(WORLD), rng.next(WORLD));
+            Rect::new(x, y, x + 1 + rng.next(100), y + 1 + rng.next(100))
+        })
+        .collect()
+}
+
+fn build_index(rects: &[Rect<i32>]) -> QuadTree<i32> {
+    let mut index = QuadTree::new(Rect::new(0, 0, WORLD + 100, WORLD + 100));
+    for rect in rects {
+        index.insert(*rect);
+    }
+    index
+}
+
+fn bench_queries(c: &mut Criterion) {
+    for &count in &[1_000, 10_000] {
+        let rects = random_rects(count);
+        let index = build_index(&rects);
+        let area = Rect::new(5_000, 5_000, 5_500, 5_500);
+
+        let mut group = c.benchmark_group(format!("{} rects", count));
+        group.bench_function(BenchmarkId::new("point", "scan"), |b| {
+            b.iter(|| spatial::scan_point(&rects, black_box(5_000), black_box(5_000)))
+        });
+        group.bench_function(BenchmarkId::new("point", "quadtree"), |b| {
+            b.iter(|| index.query_point(black_box(5_000), black_box(5_000)))
+        });
+        group.bench_function(BenchmarkId::new("rect", "scan"), |b| {
+            b.iter(|| spatial::scan_rect(&rects, black_box(&area)))
+        });
+        group.bench_function(BenchmarkId::new("rect", "quadtree"), |b| {
+            b.iter(|| index.query_rect(black_box(&area)))
+        });
+        group.bench_function(BenchmarkId::new("nearest", "scan"), |b| {
+            b.iter(|| spatial::scan_nearest(&rects, black_box(-50), black_box(5_000)))
+        });
+        group.bench_function(BenchmarkId::new("nearest", "quadtree"), |b| {
+            b.iter(|| index.nearest(black_box(-50), black_box(5_000)))
+        });
+        group.finish();
+    }
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let rects = random_rects(10_000);
+    c.bench_function("build quadtree, 10000 rects", |b| b.iter(|| build_index(black_box(&rects))));
+}
+
+criterion_group!(benches, bench_queries, bench_insert);
+criterion_main!(benches);
@@ -0,0 +1,149 @@
+use std::fmt::Debug;
+
+// Numeric type that can be used for `Rect` coordinates.
+//
+// The operations are checked: they return `None` on integer overflow
+// (and if the result is not a finite number for floats), so the `Rect`
+// methods can report it instead of panicking or silently wrapping around.
+pub trait Coord: Copy + PartialOrd + Debug {
+    fn zero() -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    // The value in the middle between `self` and `other` (rounded down
+    // for integers), it never overflows.
+    fn midpoint(self, other: Self) -> Self;
+    // Convert to f64 to compute distances (can lose precision for
+    // large 64-bit integers).
+    fn to_f64(self) -> f64;
+}
+
+// The implementation is the same for all integer types, so we generate
+// it with a macro. Integer types already have the `checked_*` methods,
+// here we just call them.
+macro_rules! impl_coord_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                fn zero() -> $t {
+                    0
+                }
+                fn checked_add(self, other: $t) -> Option<$t> {
+                    <$t>::checked_add(self, other)
+                }
+                fn checked_sub(self, other: $t) -> Option<$t> {
+                    <$t>::checked_sub(self, other)
+                }
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    <$t>::checked_mul(self, other)
+                }
+                // Any of our integer types fits into i128, so the sum
+                // can not overflow.
+                fn midpoint(self, other: $t) -> $t {
+                    ((self as i128 + other as i128).div_euclid(2)) as $t
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_coord_for_int!(i32, i64, u32, u64);
+
+// Floats don't overflow, but can become infinite (or NaN).
+macro_rules! impl_coord_for_float {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                fn zero() -> $t {
+                    0.0
+                }
+                fn checked_add(self, other: $t) -> Option<$t> {
+                    Some(self + other).filter(|value| value.is_finite())
+                }
+                fn checked_sub(self, other: $t) -> Option<$t> {
+                    Some(self - other).filter(|value| value.is_finite())
+                }
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    Some(self * other).filter(|value| value.is_finite())
+                }
+                fn midpoint(self, other: $t) -> $t {
+                    self / 2.0 + other / 2.0
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_coord_for_float!(f32, f64);
+
+// `std::cmp::min` and `max` require the `Ord` trait, which floats don't
+// implement (because of NaN), so we use `PartialOrd` versions.
+pub fn min<T: Coord>(a: T, b: T) -> T {
+    if b < a { b } else { a }
+}
+
+pub fn max<T: Coord>(a: T, b: T) -> T {
+    if b > a { b } else { a }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rect::{union_area, Rect};
+
+    #[test]
+    fn checked_integer_arithmetic() {
+        assert_eq!(Coord::checked_add(i32::MAX, 1), None);
+        assert_eq!(Coord::checked_sub(0u32, 1), None);
+        assert_eq!(Coord::checked_mul(u64::MAX, 2), None);
+        assert_eq!(Coord::checked_sub(i64::MIN, 1), None);
+        assert_eq!(Coord::checked_mul(-3i32, 4), Some(-12));
+        assert_eq!(<u32 as Coord>::zero(), 0);
+    }
+
+    #[test]
+    fn integer_midpoint_does_not_overflow() {
+        assert_eq!(Coord::midpoint(i32::MAX, i32::MAX), i32::MAX);
+        assert_eq!(Coord::midpoint(u64::MAX, u64::MAX - 2), u64::MAX - 1);
+        assert_eq!(Coord::midpoint(i32::MIN, i32::MAX), -1);
+        // Rounded down, also for negative numbers.
+        assert_eq!(Coord::midpoint(-3i32, 0), -2);
+        assert_eq!(Coord::midpoint(1u32, 4), 2);
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        assert_eq!(Coord::checked_add(0.5f64, 0.25), Some(0.75));
+        assert_eq!(Coord::checked_mul(1.5f32, 2.0), Some(3.0));
+        assert_eq!(Coord::checked_mul(f64::MAX, 2.0), None);
+        assert_eq!(Coord::checked_sub(f32::MIN, f32::MAX), None);
+        assert_eq!(Coord::checked_add(f64::INFINITY, 1.0), None);
+        assert_eq!(Coord::checked_add(f64::NAN, 1.0), None);
+        assert_eq!(Coord::midpoint(f64::MAX, f64::MAX), f64::MAX);
+        assert_eq!(Coord::midpoint(-1.0f32, 2.0), 0.5);
+        assert_eq!(Coord::to_f64(2.5f32), 2.5);
+    }
+
+    #[test]
+    fn float_rects() {
+        let rect = Rect::new(0.5, 0.5, 2.0, 1.5);
+        assert_eq!(rect.area(), Some(1.5));
+        assert!(rect.contains_point(0.5, 1.0));
+        assert!(!rect.contains_point(2.0, 1.0));
+        assert_eq!(Rect::new(-f64::MAX, 0.0, f64::MAX, 1.0).width(), None);
+        assert_eq!(union_area(&[Rect::new(0.0, 0.0, f64::MAX, 2.0), Rect::new(0.0, 2.0, 1.0, 3.0)]), None);
+    }
+
+    #[test]
+    fn partial_min_max() {
+        assert_eq!(min(1.5, -2.0), -2.0);
+        assert_eq!(max(1.5, -2.0), 1.5);
+        assert_eq!(min(3, 3), 3);
+    }
+}
@@ -0,0 +1,22 @@
+// Rectangle geometry, as a library crate so other crates can depend on
+// it (the `src/main.rs` binary is one of the users).
+//
+// The rectangle covers the `x0..x1` and `y0..y1` half-open ranges (the
+// start is included and the end is not), like a set of pixels:
+// `Rect::new(0, 0, 2, 2)` covers 4 pixels, (0, 0), (1, 0), (0, 1) and
+// (1, 1).
+// With this convention rectangles that only touch each other (share an
+// edge) do not intersect.
+pub mod coord;
+pub mod packing;
+pub mod parse;
+pub mod rect;
+pub mod render;
+pub mod spatial;
+
+pub use coord::Coord;
+pub use packing::{pack, Algorithm, Packing};
+pub use parse::{LoadError, ParseRectError};
+pub use rect::{total_area, union_area, Rect};
+pub use render::Shape;
+pub use spatial::QuadTree;
@@ -1,26 +1,114 @@
-#[derive(Debug)]
-struct Rect {
-    x0: u32,
-    y0: u32,
-    x1: u32,
-    y1: u32,
-}
+use std::env;
+use std::fmt::Display;
+use std::process;
 
-impl Rect {
-    fn area(&self) -> u32 {
-        (self.x1 - self.x0)*(self.y1-self.y0)
-    }
+use structs_rectangle::{parse, render};
+use structs_rectangle::{pack, total_area, union_area, Algorithm, Coord, QuadTree, Rect, Shape};
 
-    // Methods not necessary have `self` parameter.
-    fn square(size: u32) -> Rect {
-        Rect { x0: 0, y0: 0, x1: size, y1: size }
-    }
+fn print_stats<T: Coord + Display>(rects: &[Rect<T>]) {
+    // The areas are `None` if they don't fit into the coordinate type.
+    let show = |area: Option<T>| area.map_or(String::from("overflow"), |area| area.to_string());
+    println!("rectangles: {}", rects.len());
+    println!("total area: {}", show(total_area(rects)));
+    println!("union area: {}", show(union_area(rects)));
+}
+
+// Print the statistics for the rectangles from the file
+// (`.json`, `.csv` or text with one rectangle per line).
+// Integer coordinates are exact, so we try them first and load the file
+// with float coordinates if it has numbers like `1.5`.
+fn print_file_stats(path: &str) {
+    let err = match parse::load_file::<i64, _>(path) {
+        Ok(rects) => return print_stats(&rects),
+        Err(err) if err.is_number_error() => match parse::load_file::<f64, _>(path) {
+            Ok(rects) => return print_stats(&rects),
+            Err(err) => err,
+        },
+        Err(err) => err,
+    };
+    eprintln!("{}: {}", path, err);
+    process::exit(1);
 }
 
 fn main() {
-    let r1 = Rect{ x0: 10, y0: 10, x1: 100, y1: 100 };
-    println!("area {}", r1.area());
+    // Usage: `cargo run -- rects.csv` to load rectangles from the file,
+    // without arguments the examples below are shown.
+    if let Some(path) = env::args().nth(1) {
+        print_file_stats(&path);
+        return;
+    }
+
+    let r1 = Rect::new(10, 10, 100, 100);
+    println!("area {:?}", r1.area());
 
     let s1 = Rect::square(10);
-    println!("area {}", s1.area());
+    println!("area {:?}", s1.area());
+
+    // Corners can be given in any order, the rectangle is normalized:
+    let r2 = Rect::new(150, 150, 50, 50);
+    println!("{:?}, width {:?}, height {:?}", r2, r2.width(), r2.height());
+
+    println!("intersects: {}", r1.intersects(&r2));
+    println!("intersection: {:?}", r1.intersection(&r2));
+    println!("union: {:?}", r1.union(&r2));
+    println!("contains (20, 20): {}", r1.contains_point(20, 20));
+    println!("contains square: {}", r1.contains_rect(&s1));
+    println!("translated: {:?}", s1.translate(5, -5));
+
+    // Coordinates can be floats:
+    let f1 = Rect::new(-1.5, -1.5, 1.5, 1.5);
+    println!("{:?}, area {:?}", f1, f1.area());
+
+    // Or unsigned integers, the area is `None` if it doesn't fit into
+    // the type:
+    let u1: Rect<u32> = Rect::new(0, 0, 100_000, 100_000);
+    println!("{:?}, area {:?}", u1, u1.area());
+    println!("translated: {:?}", u1.translate(u32::MAX, 0));
+
+    // Spatial index to find rectangles by position without checking
+    // all of them:
+    let mut index = QuadTree::new(Rect::new(0, 0, 1000, 1000));
+    let a = index.insert(r1);
+    index.insert(r2);
+    index.insert(Rect::new(500, 500, 600, 600));
+    println!("at (60, 60): {:?}", index.query_point(60, 60));
+    println!("in (0, 0, 20, 20): {:?}", index.query_rect(&Rect::new(0, 0, 20, 20)));
+    println!("nearest to (700, 700): {:?}", index.nearest(700, 700));
+    index.remove(a);
+    println!("at (60, 60) after removal: {:?}", index.query_point(60, 60));
+
+    // Pack rectangles into a container, the sizes are given as
+    // rectangles (the position is ignored):
+    let sizes = vec![
+        Rect::square(40),
+        Rect::new(0, 0, 60, 20),
+        Rect::new(0, 0, 20, 50),
+        Rect::square(30),
+        Rect::square(30),
+        Rect::new(0, 0, 100, 10),
+    ];
+    let container = Rect::square(100);
+    for algorithm in [Algorithm::Shelf, Algorithm::MaxRects] {
+        let packing = pack(container, &sizes, algorithm);
+        println!("{:?}: {:?}", algorithm, packing.placed);
+        println!("unplaced: {:?}, wasted area: {:?}", packing.unplaced(), packing.wasted_area());
+    }
+
+    // Render rectangles as ASCII art (overlaps are shown with `#`)
+    // and as SVG:
+    let shapes = vec![
+        Shape::new(r1).label("r1").fill("steelblue"),
+        Shape::new(r2).label("r2").fill("orange"),
+        Shape::from(s1),
+    ];
+    print!("{}", render::to_ascii(&shapes, 40));
+    print!("{}", render::to_svg(&shapes));
+
+    // Parse rectangles from strings:
+    let parsed: Result<Rect<i32>, _> = "10,10,100,100".parse();
+    println!("{:?}", parsed);
+    let parsed: Result<Rect<i32>, _> = "90x90+10+10".parse();
+    println!("{:?}", parsed);
+    let parsed: Result<Rect<i32>, _> = "10,10,100".parse();
+    println!("{:?}", parsed);
 }
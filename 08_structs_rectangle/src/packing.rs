@@ -0,0 +1,287 @@
+// Rectangle packing: place a list of rectangles into a container
+// without overlaps (for example, to build a sprite atlas).
+//
+// The input rectangles are only used for their sizes, so they can be
+// created with `Rect::square(size)` or `Rect::new(0, 0, width, height)`.
+// The rectangles are not rotated.
+use crate::coord::Coord;
+use crate::rect::Rect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    // Rectangles are placed in rows ("shelves"), the tallest first.
+    // Fast, but wastes space above the lower rectangles in the row.
+    Shelf,
+    // Keep the list of maximal free rectangles and put each rectangle
+    // into the free rectangle where it leaves the least space on the
+    // shorter side ("best short side fit").
+    // Slower, but packs tighter.
+    MaxRects,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing<T> {
+    pub container: Rect<T>,
+    // Placed rectangles in the same order as the input,
+    // `None` if the rectangle didn't fit.
+    pub placed: Vec<Option<Rect<T>>>,
+}
+
+impl<T: Coord> Packing<T> {
+    // Indexes of the rectangles that didn't fit.
+    pub fn unplaced(&self) -> Vec<usize> {
+        (0..self.placed.len()).filter(|&i| self.placed[i].is_none()).collect()
+    }
+
+    // Total area of the placed rectangles, `None` on overflow.
+    pub fn used_area(&self) -> Option<T> {
+        self.placed.iter().flatten().try_fold(T::zero(), |sum, rect| sum.checked_add(rect.area()?))
+    }
+
+    // Container area not covered by the placed rectangles.
+    pub fn wasted_area(&self) -> Option<T> {
+        self.container.area()?.checked_sub(self.used_area()?)
+    }
+}
+
+pub fn pack<T: Coord>(container: Rect<T>, sizes: &[Rect<T>], algorithm: Algorithm) -> Packing<T> {
+    let placed = match algorithm {
+        Algorithm::Shelf => pack_shelf(&container, sizes),
+        Algorithm::MaxRects => pack_maxrects(&container, sizes),
+    };
+    Packing { container, placed }
+}
+
+// Rectangle with the size of `size` and the top left corner at (x, y).
+fn place<T: Coord>(size: &Rect<T>, x: T, y: T) -> Option<Rect<T>> {
+    Some(Rect::new(x, y, x.checked_add(size.width()?)?, y.checked_add(size.height()?)?))
+}
+
+// Indexes of the rectangles sorted by the key, largest first.
+// Floats can't be sorted with `sort_by_key` (they are not `Ord`), so we
+// use `partial_cmp`.
+fn sorted_desc<T: Coord, F: Fn(&Rect<T>) -> Option<T>>(sizes: &[Rect<T>], key: F) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| key(&sizes[b]).partial_cmp(&key(&sizes[a])).unwrap_or(std::cmp::Ordering::Equal));
+    order
+}
+
+struct Shelf<T> {
+    y: T,
+    height: T,
+    // Where the next rectangle on the shelf starts.
+    x: T,
+}
+
+fn pack_shelf<T: Coord>(container: &Rect<T>, sizes: &[Rect<T>]) -> Vec<Option<Rect<T>>> {
+    let mut placed = vec![None; sizes.len()];
+    let mut shelves: Vec<Shelf<T>> = Vec::new();
+    // Where the next shelf starts.
+    let mut next_y = container.y0();
+
+    for i in sorted_desc(sizes, |r| r.height()) {
+        let size = &sizes[i];
+        // Try the existing shelves first.
+        let existing = shelves.iter_mut().find_map(|shelf| {
+            let rect = place(size, shelf.x, shelf.y)?;
+            if size.height()? <= shelf.height && rect.x1() <= container.x1() {
+                shelf.x = rect.x1();
+                Some(rect)
+            } else {
+                None
+            }
+        });
+        if existing.is_some() {
+            placed[i] = existing;
+            continue;
+        }
+        // Then open a new shelf.
+        if let Some(rect) = place(size, container.x0(), next_y) {
+            if container.contains_rect(&rect) {
+                shelves.push(Shelf { y: rect.y0(), height: rect.height().unwrap_or(T::zero()), x: rect.x1() });
+                next_y = rect.y1();
+                placed[i] = Some(rect);
+            }
+        }
+    }
+    placed
+}
+
+fn pack_maxrects<T: Coord>(container: &Rect<T>, sizes: &[Rect<T>]) -> Vec<Option<Rect<T>>> {
+    let mut placed = vec![None; sizes.len()];
+    let mut free = vec![*container];
+
+    for i in sorted_desc(sizes, |r| r.area()) {
+        let size = &sizes[i];
+        // Find the free rectangle with the smallest leftover on the
+        // shorter side.
+        let mut best: Option<(T, Rect<T>)> = None;
+        for area in &free {
+            let rect = match place(size, area.x0(), area.y0()) {
+                Some(rect) if area.contains_rect(&rect) => rect,
+                _ => continue,
+            };
+            let (left_x, left_y) = match short_side(area, &rect) {
+                Some(leftover) => leftover,
+                None => continue,
+            };
+            let leftover = if left_x < left_y { left_x } else { left_y };
+            if best.as_ref().is_none_or(|&(best_leftover, _)| leftover < best_leftover) {
+                best = Some((leftover, rect));
+            }
+        }
+        let rect = match best {
+            Some((_, rect)) => rect,
+            None => continue,
+        };
+        placed[i] = Some(rect);
+
+        // Split the free rectangles that intersect the placed one.
+        let mut next_free = Vec::new();
+        for area in free {
+            if area.intersects(&rect) {
+                next_free.extend(split(&area, &rect));
+            } else {
+                next_free.push(area);
+            }
+        }
+        free = prune(next_free);
+    }
+    placed
+}
+
+// Leftover width and height after putting `rect` into `area`.
+fn short_side<T: Coord>(area: &Rect<T>, rect: &Rect<T>) -> Option<(T, T)> {
+    Some((area.width()?.checked_sub(rect.width()?)?, area.height()?.checked_sub(rect.height()?)?))
+}
+
+// The parts of the free `area` not covered by `used`: up to four
+// maximal rectangles (they can overlap each other).
+fn split<T: Coord>(area: &Rect<T>, used: &Rect<T>) -> Vec<Rect<T>> {
+    let mut parts = Vec::new();
+    if used.x0() > area.x0() {
+        parts.push(Rect::new(area.x0(), area.y0(), used.x0(), area.y1()));
+    }
+    if used.x1() < area.x1() {
+        parts.push(Rect::new(used.x1(), area.y0(), area.x1(), area.y1()));
+    }
+    if used.y0() > area.y0() {
+        parts.push(Rect::new(area.x0(), area.y0(), area.x1(), used.y0()));
+    }
+    if used.y1() < area.y1() {
+        parts.push(Rect::new(area.x0(), used.y1(), area.x1(), area.y1()));
+    }
+    parts
+}
+
+// Remove free rectangles contained in other free rectangles.
+fn prune<T: Coord>(free: Vec<Rect<T>>) -> Vec<Rect<T>> {
+    let mut result: Vec<Rect<T>> = Vec::new();
+    for (i, area) in free.iter().enumerate() {
+        let contained = free.iter().enumerate().any(|(j, other)| {
+            // For equal rectangles keep the first one.
+            i != j && other.contains_rect(area) && (other != area || j < i)
+        });
+        if !contained {
+            result.push(*area);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [Algorithm; 2] = [Algorithm::Shelf, Algorithm::MaxRects];
+
+    fn sizes() -> Vec<Rect<i32>> {
+        vec![
+            Rect::new(0, 0, 40, 30),
+            Rect::square(25),
+            Rect::new(0, 0, 10, 60),
+            Rect::new(0, 0, 70, 10),
+            Rect::square(5),
+            Rect::new(0, 0, 30, 20),
+            Rect::square(15),
+        ]
+    }
+
+    // Placed rects are inside of the container, have the input sizes and
+    // don't overlap.
+    fn check(packing: &Packing<i32>, sizes: &[Rect<i32>]) {
+        assert_eq!(packing.placed.len(), sizes.len());
+        let placed: Vec<(usize, Rect<i32>)> =
+            packing.placed.iter().enumerate().filter_map(|(i, rect)| rect.map(|rect| (i, rect))).collect();
+        for &(i, rect) in &placed {
+            assert!(packing.container.contains_rect(&rect), "{:?} is outside", rect);
+            assert_eq!((rect.width(), rect.height()), (sizes[i].width(), sizes[i].height()));
+        }
+        for (a, &(_, first)) in placed.iter().enumerate() {
+            for &(_, second) in &placed[a + 1..] {
+                assert!(!first.intersects(&second), "{:?} overlaps {:?}", first, second);
+            }
+        }
+    }
+
+    #[test]
+    fn everything_fits() {
+        let container = Rect::new(10, 10, 110, 110);
+        for algorithm in ALGORITHMS {
+            let packing = pack(container, &sizes(), algorithm);
+            check(&packing, &sizes());
+            assert!(packing.unplaced().is_empty(), "{:?}: {:?}", algorithm, packing.placed);
+            assert_eq!(packing.used_area(), Some(1200 + 625 + 600 + 700 + 25 + 600 + 225));
+            assert_eq!(packing.wasted_area(), Some(10_000 - 3975));
+        }
+    }
+
+    #[test]
+    fn too_large_rects_are_not_placed() {
+        let container = Rect::new(0, 0, 50, 50);
+        let sizes = vec![Rect::new(0, 0, 51, 10), Rect::square(20), Rect::new(0, 0, 10, 60), Rect::square(50)];
+        for algorithm in ALGORITHMS {
+            let packing = pack(container, &sizes, algorithm);
+            check(&packing, &sizes);
+            assert_eq!(packing.placed[0], None);
+            assert_eq!(packing.placed[2], None);
+            // The container is full after the 50x50 square.
+            assert_eq!(packing.placed[3], Some(container));
+            assert_eq!(packing.unplaced(), vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn fills_small_container() {
+        // More rects than fit, whatever fits must stay valid.
+        let sizes: Vec<Rect<i32>> = (1..=30).map(|i| Rect::new(0, 0, 3 + i % 7, 2 + i % 5)).collect();
+        for algorithm in ALGORITHMS {
+            let packing = pack(Rect::new(0, 0, 30, 20), &sizes, algorithm);
+            check(&packing, &sizes);
+            assert!(!packing.unplaced().is_empty());
+            assert!(packing.unplaced().len() < sizes.len());
+        }
+    }
+
+    #[test]
+    fn maxrects_fills_gaps() {
+        // Shelf leaves the space next to the tall rect unused.
+        let sizes = vec![Rect::new(0, 0, 10, 20), Rect::square(10), Rect::square(10)];
+        let container = Rect::new(0, 0, 20, 20);
+        let packing = pack(container, &sizes, Algorithm::MaxRects);
+        check(&packing, &sizes);
+        assert!(packing.unplaced().is_empty());
+        assert_eq!(packing.wasted_area(), Some(0));
+        assert_eq!(pack(container, &sizes, Algorithm::Shelf).unplaced().len(), 1);
+    }
+
+    #[test]
+    fn float_sizes() {
+        let sizes = vec![Rect::new(0.0, 0.0, 0.5, 0.5), Rect::new(0.0, 0.0, 0.5, 1.0), Rect::new(0.0, 0.0, 1.5, 0.1)];
+        for algorithm in ALGORITHMS {
+            let packing = pack(Rect::new(0.0, 0.0, 1.0, 1.0), &sizes, algorithm);
+            assert_eq!(packing.unplaced(), vec![2]);
+            assert!(packing.placed.iter().flatten().all(|rect| packing.container.contains_rect(rect)));
+        }
+    }
+}
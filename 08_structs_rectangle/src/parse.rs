@@ -0,0 +1,279 @@
+// Read rectangles from text.
+//
+// A single rectangle can be parsed with `str::parse` (thanks to the
+// `FromStr` implementation) in one of two formats:
+//
+//     10,10,100,100    corners: x0,y0,x1,y1
+//     90x90+10+10      geometry: WIDTHxHEIGHT+X+Y (like in X11 `-geometry`)
+//
+// Files with many rectangles can be JSON (an array of
+// `{"x0": .., "y0": .., "x1": .., "y1": ..}` objects), CSV (with the
+// `x0,y0,x1,y1` header) or plain text with one rectangle per line.
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+
+use crate::coord::Coord;
+use crate::rect::Rect;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRectError {
+    Empty,
+    // Neither `x0,y0,x1,y1` nor `WxH+X+Y`.
+    InvalidFormat(String),
+    InvalidNumber(String),
+    // `X + WIDTH` (or `Y + HEIGHT`) doesn't fit into the type.
+    Overflow,
+    // Width or height in the `WxH+X+Y` format is negative.
+    NegativeSize(String),
+}
+
+impl fmt::Display for ParseRectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRectError::Empty => write!(f, "empty rectangle definition"),
+            ParseRectError::InvalidFormat(s) => {
+                write!(f, "invalid rectangle '{}', expected x0,y0,x1,y1 or WxH+X+Y", s)
+            }
+            ParseRectError::InvalidNumber(s) => write!(f, "invalid number '{}'", s),
+            ParseRectError::Overflow => write!(f, "rectangle coordinates overflow"),
+            ParseRectError::NegativeSize(s) => write!(f, "negative size in '{}'", s),
+        }
+    }
+}
+
+impl error::Error for ParseRectError {}
+
+fn parse_number<T: FromStr>(s: &str) -> Result<T, ParseRectError> {
+    s.trim().parse().map_err(|_| ParseRectError::InvalidNumber(s.trim().to_string()))
+}
+
+// Split "+10-20" into "+10" and "-20": the second part starts at the
+// first sign after the beginning.
+fn split_offsets(s: &str) -> Option<(&str, &str)> {
+    let index = s.char_indices().skip(1).find(|&(_, c)| c == '+' || c == '-')?.0;
+    Some(s.split_at(index))
+}
+
+// Parse the `WxH+X+Y` format.
+fn parse_geometry<T: Coord + FromStr>(s: &str) -> Result<Rect<T>, ParseRectError> {
+    let invalid = || ParseRectError::InvalidFormat(s.to_string());
+    let (width, rest) = s.split_once('x').ok_or_else(invalid)?;
+    // The height ends where the offsets start (the sign at the start is
+    // the sign of the height).
+    let (height, offsets) = split_offsets(rest).ok_or_else(invalid)?;
+    let (x, y) = split_offsets(offsets).ok_or_else(invalid)?;
+
+    let (width, height): (T, T) = (parse_number(width)?, parse_number(height)?);
+    if width < T::zero() || height < T::zero() {
+        return Err(ParseRectError::NegativeSize(s.to_string()));
+    }
+    let (x, y): (T, T) = (parse_number(x)?, parse_number(y)?);
+    let x1 = x.checked_add(width).ok_or(ParseRectError::Overflow)?;
+    let y1 = y.checked_add(height).ok_or(ParseRectError::Overflow)?;
+    Ok(Rect::new(x, y, x1, y1))
+}
+
+impl<T: Coord + FromStr> FromStr for Rect<T> {
+    type Err = ParseRectError;
+
+    fn from_str(s: &str) -> Result<Rect<T>, ParseRectError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRectError::Empty);
+        }
+        if s.contains('x') {
+            return parse_geometry(s);
+        }
+        let parts: Vec<&str> = s.split(',').collect();
+        if parts.len() != 4 {
+            return Err(ParseRectError::InvalidFormat(s.to_string()));
+        }
+        Ok(Rect::new(
+            parse_number(parts[0])?,
+            parse_number(parts[1])?,
+            parse_number(parts[2])?,
+            parse_number(parts[3])?,
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    // Errors in CSV and text files are reported with the line number.
+    Csv { line: u64, error: csv::Error },
+    Parse { line: usize, error: ParseRectError },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "failed to read rectangles: {}", error),
+            LoadError::Json(error) => write!(f, "invalid JSON: {}", error),
+            // The csv error message already includes the line number.
+            LoadError::Csv { error, .. } => write!(f, "invalid CSV: {}", error),
+            LoadError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+impl LoadError {
+    // The file has a number that doesn't fit the coordinate type, like
+    // `1.5` for integer coordinates, so it may load with another type.
+    // Other errors (IO, syntax, missing fields) would fail for any type.
+    pub fn is_number_error(&self) -> bool {
+        match self {
+            LoadError::Parse { error, .. } => matches!(error, ParseRectError::InvalidNumber(_)),
+            LoadError::Csv { error, .. } => match error.kind() {
+                csv::ErrorKind::Deserialize { err, .. } => {
+                    matches!(err.kind(), csv::DeserializeErrorKind::ParseInt(_))
+                }
+                _ => false,
+            },
+            // serde_json doesn't have a separate error kind for this, only
+            // the message shows which value had the wrong type.
+            LoadError::Json(error) => error.is_data() && error.to_string().starts_with("invalid type: floating point"),
+            LoadError::Io(_) => false,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> LoadError {
+        LoadError::Io(error)
+    }
+}
+
+pub fn from_json<T, R>(reader: R) -> Result<Vec<Rect<T>>, LoadError>
+where
+    T: Coord + DeserializeOwned,
+    R: io::Read,
+{
+    serde_json::from_reader(reader).map_err(LoadError::Json)
+}
+
+pub fn from_csv<T, R>(reader: R) -> Result<Vec<Rect<T>>, LoadError>
+where
+    T: Coord + DeserializeOwned,
+    R: io::Read,
+{
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let mut rects = Vec::new();
+    for record in reader.deserialize() {
+        let rect = record.map_err(|error: csv::Error| {
+            let line = error.position().map_or(0, |position| position.line());
+            LoadError::Csv { line, error }
+        })?;
+        rects.push(rect);
+    }
+    Ok(rects)
+}
+
+// One rectangle per line, empty lines and lines starting with `#` are
+// skipped.
+pub fn from_lines<T, R>(reader: R) -> Result<Vec<Rect<T>>, LoadError>
+where
+    T: Coord + FromStr,
+    R: BufRead,
+{
+    let mut rects = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let rect = line.parse().map_err(|error| LoadError::Parse { line: index + 1, error })?;
+        rects.push(rect);
+    }
+    Ok(rects)
+}
+
+// Load rectangles from the file, the format is chosen by the file
+// extension: `.json`, `.csv` or anything else for plain text.
+pub fn load_file<T, P>(path: P) -> Result<Vec<Rect<T>>, LoadError>
+where
+    T: Coord + FromStr + DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => from_json(reader),
+        Some("csv") => from_csv(reader),
+        _ => from_lines(reader),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_corners() {
+        assert_eq!("10,20,0,0".parse(), Ok(Rect::new(0, 0, 10, 20)));
+        assert_eq!(" 1.5, 0, 3, 2 ".parse(), Ok(Rect::new(1.5, 0.0, 3.0, 2.0)));
+    }
+
+    #[test]
+    fn parses_geometry() {
+        assert_eq!("90x80+10+20".parse(), Ok(Rect::new(10, 20, 100, 100)));
+        assert_eq!("10x5-3-4".parse(), Ok(Rect::new(-3, -4, 7, 1)));
+        assert_eq!("1.5x2.5+0.5+0".parse(), Ok(Rect::new(0.5, 0.0, 2.0, 2.5)));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!("".parse::<Rect<i32>>(), Err(ParseRectError::Empty));
+        assert_eq!("1,2,3".parse::<Rect<i32>>(), Err(ParseRectError::InvalidFormat("1,2,3".to_string())));
+        assert_eq!("10x10".parse::<Rect<i32>>(), Err(ParseRectError::InvalidFormat("10x10".to_string())));
+        assert_eq!("1,2,a,4".parse::<Rect<i32>>(), Err(ParseRectError::InvalidNumber("a".to_string())));
+        assert_eq!("1.5,0,1,1".parse::<Rect<i32>>(), Err(ParseRectError::InvalidNumber("1.5".to_string())));
+        assert_eq!("10x10+2147483647+0".parse::<Rect<i32>>(), Err(ParseRectError::Overflow));
+    }
+
+    #[test]
+    fn rejects_negative_size() {
+        assert_eq!("-10x5+0+0".parse::<Rect<i32>>(), Err(ParseRectError::NegativeSize("-10x5+0+0".to_string())));
+        assert_eq!("10x-5+0+0".parse::<Rect<f64>>(), Err(ParseRectError::NegativeSize("10x-5+0+0".to_string())));
+    }
+
+    #[test]
+    fn loads_files() {
+        let text = "# comment\n0,0,10,10\n\n5x5+1+1\n";
+        assert_eq!(from_lines::<i32, _>(text.as_bytes()).unwrap(), vec![Rect::new(0, 0, 10, 10), Rect::new(1, 1, 6, 6)]);
+        match from_lines::<i32, _>("0,0,1,1\nbad\n".as_bytes()) {
+            Err(LoadError::Parse { line: 2, .. }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        let csv = "x0,y0,x1,y1\n0,0,2.5,2\n";
+        assert_eq!(from_csv::<f64, _>(csv.as_bytes()).unwrap(), vec![Rect::new(0.0, 0.0, 2.5, 2.0)]);
+        let json = r#"[{"x0": 5, "y0": 5, "x1": 0, "y1": 0}]"#;
+        assert_eq!(from_json::<i64, _>(json.as_bytes()).unwrap(), vec![Rect::new(0, 0, 5, 5)]);
+    }
+
+    #[test]
+    fn detects_number_errors() {
+        // Floats in files for integer coordinates.
+        assert!(from_lines::<i64, _>("0,0,1.5,1\n".as_bytes()).unwrap_err().is_number_error());
+        assert!(from_csv::<i64, _>("x0,y0,x1,y1\n0,0,1.5,1\n".as_bytes()).unwrap_err().is_number_error());
+        let json = r#"[{"x0": 0, "y0": 0, "x1": 1.5, "y1": 1}]"#;
+        assert!(from_json::<i64, _>(json.as_bytes()).unwrap_err().is_number_error());
+
+        // Errors that don't depend on the coordinate type.
+        assert!(!from_lines::<i64, _>("0,0,1\n".as_bytes()).unwrap_err().is_number_error());
+        assert!(!from_csv::<i64, _>("x0,y0,x1,y1\n0,0,1\n".as_bytes()).unwrap_err().is_number_error());
+        assert!(!from_json::<i64, _>(r#"[{"x0": 0}]"#.as_bytes()).unwrap_err().is_number_error());
+        assert!(!from_json::<i64, _>("[".as_bytes()).unwrap_err().is_number_error());
+        assert!(!load_file::<i64, _>("missing.txt").unwrap_err().is_number_error());
+    }
+}
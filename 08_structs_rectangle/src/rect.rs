@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+
+use crate::coord::{self, Coord};
+
+// Generic rectangle, all coordinates have the same type `T`, which
+// can be any type implementing the `Coord` trait: `i32`, `i64`, `u32`,
+// `u64`, `f32` or `f64`.
+//
+// The fields are private, the only way to create a `Rect` is
+// with `Rect::new` (or `Rect::square`), which makes sure that
+// `x0 <= x1` and `y0 <= y1`, so the width and height can not be negative.
+//
+// The `Serialize` and `Deserialize` traits are generated by serde, the
+// rectangle is stored as `{"x0": 10, "y0": 10, "x1": 100, "y1": 100}`.
+// Deserialization goes through `RectRecord` and `Rect::new`, so the loaded
+// rectangles are normalized too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RectRecord<T>", bound(deserialize = "T: Coord + Deserialize<'de>"))]
+pub struct Rect<T> {
+    x0: T,
+    y0: T,
+    x1: T,
+    y1: T,
+}
+
+// Rectangle fields as they are stored in files.
+#[derive(Deserialize)]
+struct RectRecord<T> {
+    x0: T,
+    y0: T,
+    x1: T,
+    y1: T,
+}
+
+impl<T: Coord> From<RectRecord<T>> for Rect<T> {
+    fn from(record: RectRecord<T>) -> Rect<T> {
+        Rect::new(record.x0, record.y0, record.x1, record.y1)
+    }
+}
+
+impl<T: Coord> Rect<T> {
+    // Create a rectangle from two opposite corners, in any order.
+    pub fn new(x0: T, y0: T, x1: T, y1: T) -> Rect<T> {
+        Rect {
+            x0: coord::min(x0, x1),
+            y0: coord::min(y0, y1),
+            x1: coord::max(x0, x1),
+            y1: coord::max(y0, y1),
+        }
+    }
+
+    // Methods not necessary have `self` parameter.
+    pub fn square(size: T) -> Rect<T> {
+        Rect::new(T::zero(), T::zero(), size, size)
+    }
+
+    pub fn x0(&self) -> T {
+        self.x0
+    }
+
+    pub fn y0(&self) -> T {
+        self.y0
+    }
+
+    pub fn x1(&self) -> T {
+        self.x1
+    }
+
+    pub fn y1(&self) -> T {
+        self.y1
+    }
+
+    // The width, height and area are `None` on overflow, for example,
+    // the width of `Rect::new(i32::MIN, 0, i32::MAX, 1)` doesn't fit
+    // into `i32`.
+    pub fn width(&self) -> Option<T> {
+        self.x1.checked_sub(self.x0)
+    }
+
+    pub fn height(&self) -> Option<T> {
+        self.y1.checked_sub(self.y0)
+    }
+
+    pub fn area(&self) -> Option<T> {
+        self.width()?.checked_mul(self.height()?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x0 == self.x1 || self.y0 == self.y1
+    }
+
+    pub fn contains_point(&self, x: T, y: T) -> bool {
+        self.x0 <= x && x < self.x1 && self.y0 <= y && y < self.y1
+    }
+
+    // The `other` rectangle is completely inside of this one.
+    pub fn contains_rect(&self, other: &Rect<T>) -> bool {
+        self.x0 <= other.x0 && other.x1 <= self.x1 && self.y0 <= other.y0 && other.y1 <= self.y1
+    }
+
+    // Rectangles have a common area.
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    // The common area of two rectangles, `None` if they don't intersect.
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let rect = Rect {
+            x0: coord::max(self.x0, other.x0),
+            y0: coord::max(self.y0, other.y0),
+            x1: coord::min(self.x1, other.x1),
+            y1: coord::min(self.y1, other.y1),
+        };
+        if rect.x0 < rect.x1 && rect.y0 < rect.y1 {
+            Some(rect)
+        } else {
+            None
+        }
+    }
+
+    // The bounding box: the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        Rect {
+            x0: coord::min(self.x0, other.x0),
+            y0: coord::min(self.y0, other.y0),
+            x1: coord::max(self.x1, other.x1),
+            y1: coord::max(self.y1, other.y1),
+        }
+    }
+
+    // Move the rectangle, `None` on overflow.
+    pub fn translate(&self, dx: T, dy: T) -> Option<Rect<T>> {
+        Some(Rect {
+            x0: self.x0.checked_add(dx)?,
+            y0: self.y0.checked_add(dy)?,
+            x1: self.x1.checked_add(dx)?,
+            y1: self.y1.checked_add(dy)?,
+        })
+    }
+}
+
+// Sum of the rectangle areas (overlapping parts are counted several
+// times), `None` on overflow.
+pub fn total_area<T: Coord>(rects: &[Rect<T>]) -> Option<T> {
+    rects.iter().try_fold(T::zero(), |sum, rect| sum.checked_add(rect.area()?))
+}
+
+// The area covered by the rectangles (overlapping parts are counted
+// once), `None` on overflow.
+//
+// We split the plane into vertical stripes at each `x0` and `x1` of the
+// rectangles. Inside of a stripe the covered area is the stripe width
+// multiplied by the length of the merged `y` ranges of the rectangles
+// crossing the stripe.
+pub fn union_area<T: Coord>(rects: &[Rect<T>]) -> Option<T> {
+    let by_coord = |a: &T, b: &T| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal);
+    let mut xs: Vec<T> = rects.iter().flat_map(|r| vec![r.x0, r.x1]).collect();
+    xs.sort_by(by_coord);
+    xs.dedup();
+
+    let mut area = T::zero();
+    for stripe in xs.windows(2) {
+        let (left, right) = (stripe[0], stripe[1]);
+        let mut ranges: Vec<(T, T)> = rects.iter()
+            .filter(|r| r.x0 <= left && right <= r.x1 && !r.is_empty())
+            .map(|r| (r.y0, r.y1))
+            .collect();
+        ranges.sort_by(|a, b| by_coord(&a.0, &b.0));
+
+        // Merge overlapping ranges and sum their lengths.
+        let mut covered = T::zero();
+        let mut current: Option<(T, T)> = None;
+        for (y0, y1) in ranges {
+            current = match current {
+                Some((start, end)) if y0 <= end => Some((start, coord::max(end, y1))),
+                Some((start, end)) => {
+                    covered = covered.checked_add(end.checked_sub(start)?)?;
+                    Some((y0, y1))
+                }
+                None => Some((y0, y1)),
+            };
+        }
+        if let Some((start, end)) = current {
+            covered = covered.checked_add(end.checked_sub(start)?)?;
+        }
+        area = area.checked_add(right.checked_sub(left)?.checked_mul(covered)?)?;
+    }
+    Some(area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_corners() {
+        let rect = Rect::new(10, 20, 0, 5);
+        assert_eq!((rect.x0(), rect.y0(), rect.x1(), rect.y1()), (0, 5, 10, 20));
+        assert_eq!(rect.width(), Some(10));
+        assert_eq!(rect.height(), Some(15));
+        assert_eq!(rect.area(), Some(150));
+        assert_eq!(Rect::square(3), Rect::new(0, 0, 3, 3));
+    }
+
+    #[test]
+    fn overlapping_rects() {
+        let a = Rect::new(0, 0, 4, 4);
+        let b = Rect::new(2, 2, 6, 6);
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(Rect::new(2, 2, 4, 4)));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 6, 6));
+        assert!(a.contains_rect(&Rect::new(1, 1, 3, 3)));
+        assert!(!a.contains_rect(&b));
+        assert!(a.contains_point(0, 0));
+        assert!(!a.contains_point(4, 0));
+    }
+
+    #[test]
+    fn touching_rects_do_not_intersect() {
+        let a = Rect::new(0, 0, 2, 2);
+        assert_eq!(a.intersection(&Rect::new(2, 0, 4, 2)), None);
+        assert_eq!(a.intersection(&Rect::new(0, 2, 2, 4)), None);
+        assert_eq!(a.intersection(&Rect::new(2, 2, 4, 4)), None);
+        assert_eq!(union_area(&[a, Rect::new(2, 0, 4, 2)]), Some(8));
+    }
+
+    #[test]
+    fn empty_and_degenerate_rects() {
+        let line = Rect::new(0, 0, 5, 0);
+        let point = Rect::new(3, 3, 3, 3);
+        assert!(line.is_empty());
+        assert!(point.is_empty());
+        assert_eq!(line.area(), Some(0));
+        assert!(!point.contains_point(3, 3));
+        assert!(!Rect::new(0, 0, 10, 10).intersects(&line));
+        assert_eq!(total_area::<i32>(&[]), Some(0));
+        assert_eq!(union_area::<i32>(&[]), Some(0));
+        assert_eq!(union_area(&[line, point]), Some(0));
+        assert_eq!(union_area(&[line, Rect::new(0, 0, 2, 2)]), Some(4));
+    }
+
+    #[test]
+    fn areas() {
+        let rects = [Rect::new(0, 0, 4, 4), Rect::new(2, 2, 6, 6), Rect::new(1, 1, 3, 3)];
+        assert_eq!(total_area(&rects), Some(16 + 16 + 4));
+        assert_eq!(union_area(&rects), Some(16 + 16 - 4));
+        // Nested rects are counted once.
+        assert_eq!(union_area(&[Rect::new(0, 0, 10, 10), Rect::new(2, 2, 3, 3)]), Some(100));
+        assert_eq!(union_area(&[Rect::new(0.0, 0.0, 1.5, 2.0), Rect::new(1.0, 0.0, 2.0, 1.0)]), Some(3.5));
+    }
+
+    // Count the covered cells one by one.
+    fn brute_force_area(rects: &[Rect<i32>]) -> i32 {
+        let mut area = 0;
+        for x in 0..20 {
+            for y in 0..20 {
+                if rects.iter().any(|rect| rect.contains_point(x, y)) {
+                    area += 1;
+                }
+            }
+        }
+        area
+    }
+
+    #[test]
+    fn union_area_matches_brute_force() {
+        // Pseudo-random rects inside of 20x20, the same every run.
+        let mut seed: u32 = 12345;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 16) % 21) as i32
+        };
+        for _ in 0..50 {
+            let rects: Vec<Rect<i32>> = (0..5).map(|_| Rect::new(next(), next(), next(), next())).collect();
+            assert_eq!(union_area(&rects), Some(brute_force_area(&rects)), "{:?}", rects);
+        }
+    }
+
+    #[test]
+    fn none_on_overflow() {
+        let wide = Rect::new(i32::MIN, 0, i32::MAX, 1);
+        assert_eq!(wide.width(), None);
+        assert_eq!(wide.area(), None);
+        assert_eq!(Rect::new(0, 0, 50_000, 50_000).area(), None);
+        assert_eq!(Rect::new(0, 0, 10, 10).translate(i32::MAX, 0), None);
+        assert_eq!(Rect::new(0, 0, 10, 10).translate(5, -5), Some(Rect::new(5, -5, 15, 5)));
+        let big = Rect::new(0, 0, 40_000, 40_000);
+        assert_eq!(big.area(), Some(1_600_000_000));
+        assert_eq!(total_area(&[big, big]), None);
+        assert_eq!(union_area(&[big, Rect::new(40_000, 0, 80_000, 40_000)]), None);
+        assert_eq!(union_area(&[big, big]), Some(1_600_000_000));
+    }
+}
@@ -0,0 +1,226 @@
+// Render rectangles as an SVG document or as ASCII art (to debug
+// layouts and overlaps).
+use std::fmt::Write;
+
+use crate::coord::Coord;
+use crate::rect::Rect;
+
+// Rectangle with optional label and fill colour (any SVG colour, like
+// "red" or "#ff8800").
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape<T> {
+    pub rect: Rect<T>,
+    pub label: Option<String>,
+    pub fill: Option<String>,
+}
+
+impl<T: Coord> Shape<T> {
+    pub fn new(rect: Rect<T>) -> Shape<T> {
+        Shape { rect, label: None, fill: None }
+    }
+
+    // The methods take `self` and return the updated shape, so they
+    // can be chained: `Shape::new(rect).label("a").fill("red")`.
+    pub fn label(mut self, label: &str) -> Shape<T> {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn fill(mut self, fill: &str) -> Shape<T> {
+        self.fill = Some(fill.to_string());
+        self
+    }
+}
+
+// Implementing `From` allows to pass plain rectangles where shapes are
+// expected: `Shape::from(rect)` or `rect.into()`.
+impl<T: Coord> From<Rect<T>> for Shape<T> {
+    fn from(rect: Rect<T>) -> Shape<T> {
+        Shape::new(rect)
+    }
+}
+
+// The area covered by all shapes as (x0, y0, x1, y1) floats.
+fn bounds<T: Coord>(shapes: &[Shape<T>]) -> Option<(f64, f64, f64, f64)> {
+    let first = shapes.first()?.rect;
+    let all = shapes.iter().fold(first, |all, shape| all.union(&shape.rect));
+    Some((all.x0().to_f64(), all.y0().to_f64(), all.x1().to_f64(), all.y1().to_f64()))
+}
+
+// Replace the characters that have special meaning in XML.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// The y axis points down, as in SVG and on the screen: smaller `y` is
+// drawn higher, the same way `to_ascii` draws the first rows.
+pub fn to_svg<T: Coord>(shapes: &[Shape<T>]) -> String {
+    let (x0, y0, x1, y1) = bounds(shapes).unwrap_or((0.0, 0.0, 0.0, 0.0));
+    // Leave some space around, so the borders are fully visible.
+    let margin = ((x1 - x0).max(y1 - y0) * 0.05).max(1.0);
+    let mut svg = String::new();
+    // `write!` and `writeln!` to a `String` can not fail, so we ignore
+    // the result.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        x0 - margin, y0 - margin, x1 - x0 + 2.0 * margin, y1 - y0 + 2.0 * margin
+    );
+    for shape in shapes {
+        let r = &shape.rect;
+        let (x, y) = (r.x0().to_f64(), r.y0().to_f64());
+        let (width, height) = (r.x1().to_f64() - x, r.y1().to_f64() - y);
+        let fill = shape.fill.as_ref().map(|fill| escape_xml(fill)).unwrap_or_else(|| String::from("none"));
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="0.5" stroke="black" vector-effect="non-scaling-stroke"/>"#,
+            x, y, width, height, fill
+        );
+        if let Some(ref label) = shape.label {
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-size="{}">{}</text>"#,
+                x + width / 2.0, y + height / 2.0, (height / 3.0).max(1.0), escape_xml(label)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Character to draw the shape with: a letter by the shape index, the
+// legend maps letters to labels.
+fn shape_char(index: usize) -> char {
+    (b'A' + (index % 26) as u8) as char
+}
+
+// The maximum number of rows in the ASCII grid.
+pub const MAX_ROWS: usize = 200;
+
+// Draw the shapes on a grid `columns` characters wide, followed by the
+// legend.
+// Each cell shows the shape covering the cell center, `#` where shapes
+// overlap and `.` for empty space. Terminal characters are about twice
+// as tall as they are wide, so the grid has half as many rows.
+pub fn to_ascii<T: Coord>(shapes: &[Shape<T>], columns: usize) -> String {
+    let (x0, y0, x1, y1) = match bounds(shapes) {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let columns = columns.max(1);
+    let (width, height) = (x1 - x0, y1 - y0);
+    let cell = width / columns as f64;
+    // A scene with zero width or height covers nothing, one row is
+    // enough to show it. Tall and thin scenes are squeezed into
+    // `MAX_ROWS` rows, otherwise the grid could have millions of rows.
+    let rows = if width > 0.0 && height > 0.0 {
+        ((height / cell / 2.0).ceil() as usize).clamp(1, MAX_ROWS)
+    } else {
+        1
+    };
+    let cell_height = height / rows as f64;
+
+    let mut text = String::new();
+    for row in 0..rows {
+        let y = y0 + (row as f64 + 0.5) * cell_height;
+        for column in 0..columns {
+            let x = x0 + (column as f64 + 0.5) * cell;
+            let mut covering = shapes.iter().enumerate().filter(|(_, shape)| {
+                let r = &shape.rect;
+                r.x0().to_f64() <= x && x < r.x1().to_f64() && r.y0().to_f64() <= y && y < r.y1().to_f64()
+            });
+            let c = match (covering.next(), covering.next()) {
+                (None, _) => '.',
+                (Some((index, _)), None) => shape_char(index),
+                (Some(_), Some(_)) => '#',
+            };
+            text.push(c);
+        }
+        text.push('\n');
+    }
+    for (index, shape) in shapes.iter().enumerate() {
+        let r = &shape.rect;
+        let _ = writeln!(
+            text,
+            "{}: {} ({:?}, {:?}) - ({:?}, {:?})",
+            shape_char(index), shape.label.as_deref().unwrap_or(""), r.x0(), r.y0(), r.x1(), r.y1()
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rect::Rect;
+
+    fn grid_rows(text: &str) -> usize {
+        text.lines().take_while(|line| !line.contains(':')).count()
+    }
+
+    #[test]
+    fn svg_has_view_box_and_rects() {
+        let shapes = [
+            Shape::new(Rect::new(0, 0, 10, 2)).label("top").fill("red"),
+            Shape::new(Rect::new(0, 8, 10, 10)).label("a < b"),
+            Shape::new(Rect::new(4, 4, 6, 6)),
+        ];
+        let svg = to_svg(&shapes);
+        // The bounds (0, 0) - (10, 10) with the margin of 1.
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -1 12 12">"#));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), shapes.len());
+        assert_eq!(svg.matches("<text ").count(), 2);
+        assert!(svg.contains(r#"<rect x="0" y="0" width="10" height="2" fill="red""#));
+        assert!(svg.contains(r#"<rect x="4" y="4" width="2" height="2" fill="none""#));
+        assert!(svg.contains(">a &lt; b</text>"));
+    }
+
+    #[test]
+    fn svg_and_ascii_keep_y_axis_down() {
+        let shapes = [Shape::new(Rect::new(0, 0, 10, 2)), Shape::new(Rect::new(0, 8, 10, 10))];
+        // In SVG the `y` coordinates are used as they are, the first
+        // shape is above the second one.
+        let svg = to_svg(&shapes);
+        let first = svg.find(r#"<rect x="0" y="0" width="10" height="2""#).unwrap();
+        let second = svg.find(r#"<rect x="0" y="8" width="10" height="2""#).unwrap();
+        assert!(first < second);
+        // The ASCII grid shows it the same way: the first row is `y0`.
+        let text = to_ascii(&shapes, 10);
+        let rows: Vec<&str> = text.lines().take(grid_rows(&text)).collect();
+        assert_eq!(rows.first(), Some(&"AAAAAAAAAA"));
+        assert_eq!(rows.last(), Some(&"BBBBBBBBBB"));
+    }
+
+    #[test]
+    fn svg_of_no_shapes_is_empty_document() {
+        let svg = to_svg::<i32>(&[]);
+        assert_eq!(svg.matches("<rect ").count(), 0);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn ascii_draws_shapes_and_overlaps() {
+        let shapes = [Shape::new(Rect::new(0, 0, 4, 4)), Shape::new(Rect::new(2, 0, 6, 4))];
+        let text = to_ascii(&shapes, 6);
+        assert!(text.starts_with("AA##BB\n"));
+        assert!(text.contains("A:  (0, 0) - (4, 4)"));
+    }
+
+    #[test]
+    fn ascii_handles_zero_width_and_height() {
+        let text = to_ascii(&[Shape::new(Rect::new(0, 0, 0, 10))], 40);
+        assert_eq!(grid_rows(&text), 1);
+        let text = to_ascii(&[Shape::new(Rect::new(0, 5, 10, 5))], 40);
+        assert_eq!(grid_rows(&text), 1);
+    }
+
+    #[test]
+    fn ascii_limits_rows_of_tall_scenes() {
+        let text = to_ascii(&[Shape::new(Rect::new(0, 0, 1, 1_000_000))], 80);
+        assert_eq!(grid_rows(&text), MAX_ROWS);
+    }
+}
@@ -0,0 +1,395 @@
+// Spatial index (quadtree) for fast lookup of rectangles by position.
+//
+// The index covers the `bounds` area, which is recursively split into
+// four quadrants. Each rectangle is stored in the smallest node that
+// fully contains it, so a query only needs to visit nodes that
+// intersect the query area instead of checking all rectangles.
+// Rectangles outside of the bounds are also accepted, they are kept in
+// the root node (and checked by every query).
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::coord::Coord;
+use crate::rect::Rect;
+
+// Split the node when it has more items than this (and merge the
+// children back when the node and its children have this many or fewer).
+const NODE_CAPACITY: usize = 8;
+// And don't split deeper than this.
+const MAX_DEPTH: usize = 16;
+
+// Identifier of the rectangle in the index, returned by `insert`.
+pub type ItemId = usize;
+
+struct Node<T> {
+    bounds: Rect<T>,
+    depth: usize,
+    items: Vec<(ItemId, Rect<T>)>,
+    // Number of items in this node and all its children.
+    count: usize,
+    // Four quadrants or nothing (for leaf nodes).
+    children: Vec<Node<T>>,
+    // The bounds are too small to split (for integer coordinates), so
+    // we don't try again on every insert.
+    unsplittable: bool,
+}
+
+impl<T: Coord> Node<T> {
+    fn new(bounds: Rect<T>, depth: usize) -> Node<T> {
+        Node { bounds, depth, items: Vec::new(), count: 0, children: Vec::new(), unsplittable: false }
+    }
+
+    // The child that fully contains the rectangle, if any.
+    fn child_for(&mut self, rect: &Rect<T>) -> Option<&mut Node<T>> {
+        self.children.iter_mut().find(|child| child.bounds.contains_rect(rect))
+    }
+
+    fn insert(&mut self, id: ItemId, rect: Rect<T>) {
+        self.count += 1;
+        if let Some(child) = self.child_for(&rect) {
+            return child.insert(id, rect);
+        }
+        self.items.push((id, rect));
+        let can_split = self.children.is_empty() && !self.unsplittable && self.depth < MAX_DEPTH;
+        if can_split && self.items.len() > NODE_CAPACITY {
+            self.split();
+        }
+    }
+
+    fn split(&mut self) {
+        let b = self.bounds;
+        let mx = b.x0().midpoint(b.x1());
+        let my = b.y0().midpoint(b.y1());
+        // Integer bounds can be too small to split.
+        if mx == b.x0() || my == b.y0() {
+            self.unsplittable = true;
+            return;
+        }
+        let depth = self.depth + 1;
+        self.children = vec![
+            Node::new(Rect::new(b.x0(), b.y0(), mx, my), depth),
+            Node::new(Rect::new(mx, b.y0(), b.x1(), my), depth),
+            Node::new(Rect::new(b.x0(), my, mx, b.y1()), depth),
+            Node::new(Rect::new(mx, my, b.x1(), b.y1()), depth),
+        ];
+        // Move items down to the children where possible (the `count`
+        // of this node stays the same).
+        for (id, rect) in std::mem::take(&mut self.items) {
+            match self.child_for(&rect) {
+                Some(child) => child.insert(id, rect),
+                None => self.items.push((id, rect)),
+            }
+        }
+    }
+
+    fn remove(&mut self, id: ItemId, rect: &Rect<T>) -> bool {
+        let removed = match self.items.iter().position(|&(item_id, _)| item_id == id) {
+            Some(index) => {
+                self.items.swap_remove(index);
+                true
+            }
+            None => self.child_for(rect).is_some_and(|child| child.remove(id, rect)),
+        };
+        if removed {
+            self.count -= 1;
+            if !self.children.is_empty() && self.count <= NODE_CAPACITY {
+                self.merge();
+            }
+        }
+        removed
+    }
+
+    // Move all items from the children to this node and drop the
+    // children, so the tree shrinks when items are removed.
+    fn merge(&mut self) {
+        for mut child in std::mem::take(&mut self.children) {
+            child.merge();
+            self.items.append(&mut child.items);
+        }
+    }
+
+    fn query_point(&self, x: T, y: T, found: &mut Vec<ItemId>) {
+        for &(id, ref rect) in &self.items {
+            if rect.contains_point(x, y) {
+                found.push(id);
+            }
+        }
+        for child in &self.children {
+            if child.bounds.contains_point(x, y) {
+                child.query_point(x, y, found);
+            }
+        }
+    }
+
+    fn query_rect(&self, area: &Rect<T>, found: &mut Vec<ItemId>) {
+        for &(id, ref rect) in &self.items {
+            if rect.intersects(area) {
+                found.push(id);
+            }
+        }
+        for child in &self.children {
+            if child.bounds.intersects(area) {
+                child.query_rect(area, found);
+            }
+        }
+    }
+}
+
+// Distance from the point to the rectangle, zero if the point is inside.
+pub fn distance_to_point<T: Coord>(rect: &Rect<T>, x: T, y: T) -> f64 {
+    let (x, y) = (x.to_f64(), y.to_f64());
+    let dx = (rect.x0().to_f64() - x).max(x - rect.x1().to_f64()).max(0.0);
+    let dy = (rect.y0().to_f64() - y).max(y - rect.y1().to_f64()).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// A node to visit in the nearest neighbour search, ordered by the
+// distance so the `BinaryHeap` (which is a max-heap) returns the
+// closest node first.
+struct Candidate<'a, T> {
+    distance: f64,
+    node: &'a Node<T>,
+}
+
+impl<'a, T> PartialEq for Candidate<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl<'a, T> Eq for Candidate<'a, T> {}
+
+impl<'a, T> PartialOrd for Candidate<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T> Ord for Candidate<'a, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed: smaller distance is "greater".
+        other.distance.partial_cmp(&self.distance).unwrap_or(Ordering::Equal)
+    }
+}
+
+pub struct QuadTree<T> {
+    root: Node<T>,
+    // All rectangles by id, to find them on removal.
+    rects: HashMap<ItemId, Rect<T>>,
+    next_id: ItemId,
+}
+
+impl<T: Coord> QuadTree<T> {
+    pub fn new(bounds: Rect<T>) -> QuadTree<T> {
+        QuadTree { root: Node::new(bounds, 0), rects: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&Rect<T>> {
+        self.rects.get(&id)
+    }
+
+    pub fn insert(&mut self, rect: Rect<T>) -> ItemId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.rects.insert(id, rect);
+        self.root.insert(id, rect);
+        id
+    }
+
+    pub fn remove(&mut self, id: ItemId) -> Option<Rect<T>> {
+        let rect = self.rects.remove(&id)?;
+        self.root.remove(id, &rect);
+        Some(rect)
+    }
+
+    // Rectangles containing the point.
+    // Note: the node always checks its own items and only visits the
+    // children that match, so the root items outside of the bounds are
+    // also checked.
+    pub fn query_point(&self, x: T, y: T) -> Vec<ItemId> {
+        let mut found = Vec::new();
+        self.root.query_point(x, y, &mut found);
+        found
+    }
+
+    // Rectangles intersecting the area.
+    pub fn query_rect(&self, area: &Rect<T>) -> Vec<ItemId> {
+        let mut found = Vec::new();
+        self.root.query_rect(area, &mut found);
+        found
+    }
+
+    // The rectangle closest to the point and the distance to it.
+    pub fn nearest(&self, x: T, y: T) -> Option<(ItemId, f64)> {
+        let mut best: Option<(ItemId, f64)> = None;
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate { distance: 0.0, node: &self.root });
+
+        while let Some(Candidate { distance, node }) = heap.pop() {
+            // All the remaining nodes are further than the best match.
+            if let Some((_, best_distance)) = best {
+                if best_distance <= distance {
+                    break;
+                }
+            }
+            for &(id, ref rect) in &node.items {
+                let d = distance_to_point(rect, x, y);
+                if best.is_none_or(|(_, best_distance)| d < best_distance) {
+                    best = Some((id, d));
+                }
+            }
+            for child in &node.children {
+                heap.push(Candidate { distance: distance_to_point(&child.bounds, x, y), node: child });
+            }
+        }
+        best
+    }
+}
+
+// Naive implementations, for comparison with the index: check all the
+// rectangles one by one.
+pub fn scan_point<T: Coord>(rects: &[Rect<T>], x: T, y: T) -> Vec<ItemId> {
+    (0..rects.len()).filter(|&i| rects[i].contains_point(x, y)).collect()
+}
+
+pub fn scan_rect<T: Coord>(rects: &[Rect<T>], area: &Rect<T>) -> Vec<ItemId> {
+    (0..rects.len()).filter(|&i| rects[i].intersects(area)).collect()
+}
+
+pub fn scan_nearest<T: Coord>(rects: &[Rect<T>], x: T, y: T) -> Option<(ItemId, f64)> {
+    let mut best: Option<(ItemId, f64)> = None;
+    for (id, rect) in rects.iter().enumerate() {
+        let d = distance_to_point(rect, x, y);
+        if best.is_none_or(|(_, best_distance)| d < best_distance) {
+            best = Some((id, d));
+        }
+    }
+    best
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pseudo-random rects in 0..1000, the same every run.
+    fn rects(count: usize) -> Vec<Rect<i32>> {
+        let mut seed: u32 = 42;
+        let mut next = |max: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 8) % max) as i32
+        };
+        (0..count)
+            .map(|_| {
+                let (x, y) = (next(1000), next(1000));
+                Rect::new(x, y, x + next(50) + 1, y + next(50) + 1)
+            })
+            .collect()
+    }
+
+    fn sorted(mut ids: Vec<ItemId>) -> Vec<ItemId> {
+        ids.sort();
+        ids
+    }
+
+    // Compare all queries with the `scan_*` helpers. The removed rects
+    // are replaced with empty ones far away, so the ids still match.
+    fn check(tree: &QuadTree<i32>, rects: &[Rect<i32>]) {
+        for (x, y) in [(0, 0), (500, 500), (999, 10), (250, 750), (1200, -30)] {
+            assert_eq!(sorted(tree.query_point(x, y)), scan_point(rects, x, y));
+            let area = Rect::new(x - 60, y - 60, x + 60, y + 60);
+            assert_eq!(sorted(tree.query_rect(&area)), scan_rect(rects, &area));
+            let nearest = tree.nearest(x, y).map(|(_, distance)| distance);
+            assert_eq!(nearest, scan_nearest(rects, x, y).map(|(_, distance)| distance));
+        }
+    }
+
+    fn count_nodes<T>(node: &Node<T>) -> usize {
+        1 + node.children.iter().map(count_nodes).sum::<usize>()
+    }
+
+    #[test]
+    fn queries_match_scan() {
+        let rects = rects(500);
+        let mut tree = QuadTree::new(Rect::new(0, 0, 1024, 1024));
+        for rect in &rects {
+            tree.insert(*rect);
+        }
+        // Outside of the bounds, kept in the root.
+        let mut rects = rects;
+        rects.push(Rect::new(1100, -50, 1300, -10));
+        tree.insert(rects[500]);
+        assert_eq!(tree.len(), 501);
+        assert!(!tree.root.children.is_empty());
+        check(&tree, &rects);
+    }
+
+    #[test]
+    fn queries_match_scan_after_remove() {
+        let mut rects = rects(300);
+        let mut tree = QuadTree::new(Rect::new(0, 0, 1024, 1024));
+        for rect in &rects {
+            tree.insert(*rect);
+        }
+        let gone = Rect::new(-10_000, -10_000, -10_000, -10_000);
+        for id in (0..300).step_by(3) {
+            assert_eq!(tree.remove(id), Some(rects[id]));
+            rects[id] = gone;
+        }
+        assert_eq!(tree.remove(0), None);
+        assert_eq!(tree.len(), 200);
+        check(&tree, &rects);
+    }
+
+    #[test]
+    fn merges_children_when_items_are_removed() {
+        let rects = rects(100);
+        let mut tree = QuadTree::new(Rect::new(0, 0, 1024, 1024));
+        let ids: Vec<ItemId> = rects.iter().map(|rect| tree.insert(*rect)).collect();
+        assert!(count_nodes(&tree.root) > 5);
+        for &id in &ids[NODE_CAPACITY..] {
+            tree.remove(id);
+        }
+        // The remaining items fit into the root again.
+        assert_eq!(count_nodes(&tree.root), 1);
+        assert_eq!(tree.root.count, NODE_CAPACITY);
+        assert_eq!(sorted(tree.query_rect(&Rect::new(0, 0, 1024, 1024))), ids[..NODE_CAPACITY].to_vec());
+        for &id in &ids[..NODE_CAPACITY] {
+            tree.remove(id);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.nearest(0, 0), None);
+    }
+
+    #[test]
+    fn small_node_is_not_split_again() {
+        // 1x1 bounds can not be split.
+        let mut tree = QuadTree::new(Rect::new(0, 0, 1, 1));
+        for _ in 0..=NODE_CAPACITY {
+            tree.insert(Rect::new(0, 0, 1, 1));
+        }
+        assert!(tree.root.unsplittable);
+        assert!(tree.root.children.is_empty());
+        tree.insert(Rect::new(0, 0, 1, 1));
+        assert_eq!(tree.query_point(0, 0).len(), NODE_CAPACITY + 2);
+    }
+
+    #[test]
+    fn float_coordinates() {
+        let mut tree = QuadTree::new(Rect::new(0.0, 0.0, 1.0, 1.0));
+        let a = tree.insert(Rect::new(0.1, 0.1, 0.2, 0.2));
+        let b = tree.insert(Rect::new(0.7, 0.7, 0.9, 0.8));
+        assert_eq!(tree.query_point(0.15, 0.15), vec![a]);
+        let (nearest, distance) = tree.nearest(1.0, 0.8).unwrap();
+        assert_eq!(nearest, b);
+        assert!((distance - 0.1).abs() < 1e-12);
+    }
+}
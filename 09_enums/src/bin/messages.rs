@@ -0,0 +1,175 @@
+// Read text commands from the input and dispatch them to the handlers:
+//
+//     $ cargo run --bin messages
+//     > move 10 100
+//     Moved to (10, 100)
+//     > color 255 0 0
+//     Color is #ff0000
+//     > quit
+//
+// Options:
+//     --verbose           show each command in JSON and binary form
+//     --log <file>        record the commands to the event log file
+//                         (new commands are appended to the existing log)
+//     --replay <file>     rebuild the state from the log and show it
+//     --from <seq>        with --replay: only show the events after `seq`
+//                         and the state built from the snapshot at `seq`
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+
+use enums::{Dispatcher, EventLog, Message, MessageHandler, Snapshot};
+
+// Prints what each message does.
+struct Console;
+
+impl MessageHandler for Console {
+    fn quit(&mut self) {
+        println!("Bye!");
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        println!("Moved to ({}, {})", x, y);
+    }
+
+    fn write(&mut self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn change_color(&mut self, r: i32, g: i32, b: i32) {
+        println!("Color is #{:02x}{:02x}{:02x}", r, g, b);
+    }
+}
+
+// Shows the encoded message, only `handle` is implemented because it is
+// interested in all messages.
+struct Encoder;
+
+impl MessageHandler for Encoder {
+    fn handle(&mut self, message: &Message) {
+        let bytes: Vec<String> = message.encode().iter().map(|byte| format!("{:02x}", byte)).collect();
+        println!("  json: {}", message.to_json());
+        println!("  binary: {}", bytes.join(" "));
+    }
+}
+
+// Appends each message to the log file as soon as it is dispatched, so
+// the events are saved even if the program is interrupted.
+struct FileLog {
+    log: EventLog,
+    file: File,
+    path: String,
+}
+
+impl MessageHandler for FileLog {
+    fn handle(&mut self, message: &Message) {
+        if let Err(err) = self.log.record(message.clone(), &mut self.file) {
+            fail(format!("can not write {}: {}", self.path, err));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    verbose: bool,
+    log_file: Option<String>,
+    replay_file: Option<String>,
+    from: u64,
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(format!("{} needs a value", arg)));
+        match arg.as_str() {
+            "--verbose" => options.verbose = true,
+            "--log" => options.log_file = Some(value()),
+            "--replay" => options.replay_file = Some(value()),
+            "--from" => {
+                let from = value();
+                options.from = from.parse().unwrap_or_else(|_| fail(format!("invalid sequence number '{}'", from)));
+            }
+            _ => fail(format!("unknown option '{}'", arg)),
+        }
+    }
+    options
+}
+
+fn load_log(path: &str) -> EventLog {
+    let file = File::open(path).unwrap_or_else(|err| fail(format!("can not open {}: {}", path, err)));
+    EventLog::read_from(BufReader::new(file)).unwrap_or_else(|err| fail(format!("{}: {}", path, err)))
+}
+
+fn replay(path: &str, from: u64) {
+    let log = load_log(path);
+    // In a real system the snapshot would be stored, here we build it
+    // from the log to show that the result is the same.
+    let snapshot = Snapshot { seq: from, state: log.replay_until(from) };
+    for event in log.events_after(from) {
+        println!("#{} at {} ms: {}", event.seq, event.timestamp_ms, event.message);
+    }
+    println!("{:#?}", log.replay_from(&snapshot));
+}
+
+fn main() {
+    let options = parse_args();
+    if let Some(path) = &options.replay_file {
+        replay(path, options.from);
+        return;
+    }
+
+    let mut dispatcher = Dispatcher::new();
+    if options.verbose {
+        dispatcher.register(Encoder);
+    }
+    dispatcher.register(Console);
+    if let Some(path) = &options.log_file {
+        // The log file is read to continue the sequence numbers, the new
+        // events are appended to it.
+        let log = match File::open(path) {
+            Ok(_) => load_log(path),
+            Err(_) => EventLog::new(),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|err| fail(format!("can not open {}: {}", path, err)));
+        dispatcher.register(FileLog { log, file, path: path.clone() });
+    }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("failed to flush stdout");
+        line.clear();
+        match input.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Failed to read input: {}", err);
+                break;
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<Message>() {
+            Ok(message) => {
+                if !dispatcher.dispatch(&message) {
+                    break;
+                }
+            }
+            Err(err) => println!("Error: {}", err),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Subnet calculator for address planning:
+//
+//     cargo run --bin subnet -- info 10.0.0.0/8
+//     cargo run --bin subnet -- contains 10.0.0.0/8 10.1.2.3 192.168.0.1
+//     cargo run --bin subnet -- split 192.168.0.0/24 26
+//     cargo run --bin subnet -- aggregate 10.0.0.0/25 10.0.0.128/25 10.0.1.0/24
+//     cargo run --bin subnet -- hosts 192.168.0.0/29
+use std::env;
+use std::process;
+
+use enums::{aggregate, IpAddr, Network};
+
+// Don't print millions of lines by accident, `hosts` and `split` show
+// only this many items unless `--all` is given.
+const DEFAULT_LIMIT: usize = 256;
+
+fn usage() -> ! {
+    eprintln!("Usage: subnet <command> [arguments]");
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!("  info <network>...               show network details");
+    eprintln!("  contains <network> <address>... check if the addresses are in the network");
+    eprintln!("  split <network> <prefix> [--all] split the network into subnets");
+    eprintln!("  aggregate <network>...          merge networks into the smallest list");
+    eprintln!("  hosts <network> [--all]         list host addresses");
+    process::exit(2);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+fn parse_network(text: &str) -> Network {
+    text.parse().unwrap_or_else(|err| fail(format!("'{}': {}", text, err)))
+}
+
+fn print_info(network: &Network) {
+    let (first, last) = network.host_range();
+    println!("Network:   {}", network);
+    println!("Netmask:   {}", network.netmask());
+    println!("Address:   {}", network.network());
+    if network.is_ipv4() {
+        println!("Broadcast: {}", network.broadcast());
+    } else {
+        println!("Last:      {}", network.broadcast());
+    }
+    println!("Hosts:     {} - {}", first, last);
+    println!("Size:      {}", network.size());
+}
+
+// Print the items, but no more than `limit` if it is set.
+fn print_limited<T: std::fmt::Display>(items: impl Iterator<Item = T>, limit: Option<usize>) {
+    for (count, item) in items.enumerate() {
+        if Some(count) == limit {
+            println!("... (use --all to show everything)");
+            return;
+        }
+        println!("{}", item);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let show_all = args.iter().any(|arg| arg == "--all");
+    let limit = if show_all { None } else { Some(DEFAULT_LIMIT) };
+    let args: Vec<&str> = args.iter().map(String::as_str).filter(|&arg| arg != "--all").collect();
+
+    match args.as_slice() {
+        ["info", networks @ ..] if !networks.is_empty() => {
+            for (index, text) in networks.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print_info(&parse_network(text));
+            }
+        }
+        ["contains", network, addresses @ ..] if !addresses.is_empty() => {
+            let network = parse_network(network);
+            for text in addresses {
+                let addr: IpAddr = text.parse().unwrap_or_else(|err| fail(format!("'{}': {}", text, err)));
+                let answer = if network.contains(&addr) { "yes" } else { "no" };
+                println!("{} in {}: {}", addr, network, answer);
+            }
+        }
+        ["split", network, prefix] => {
+            let network = parse_network(network);
+            let prefix: u8 = prefix.parse().unwrap_or_else(|_| fail(format!("invalid prefix '{}'", prefix)));
+            match network.subnets(prefix) {
+                Some(subnets) => print_limited(subnets, limit),
+                None => fail(format!("can not split {} into /{} subnets", network, prefix)),
+            }
+        }
+        ["aggregate", networks @ ..] if !networks.is_empty() => {
+            let networks: Vec<Network> = networks.iter().map(|text| parse_network(text)).collect();
+            for network in aggregate(&networks) {
+                println!("{}", network);
+            }
+        }
+        ["hosts", network] => print_limited(parse_network(network).hosts(), limit),
+        _ => usage(),
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+// US coins, like the `Coin` enum in `src/main.rs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Coin {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter,
+}
+
+impl Coin {
+    pub const ALL: [Coin; 4] = [Coin::Penny, Coin::Nickel, Coin::Dime, Coin::Quarter];
+
+    pub fn value_in_cents(self) -> u32 {
+        match self {
+            Coin::Penny => 1,
+            Coin::Nickel => 5,
+            Coin::Dime => 10,
+            Coin::Quarter => 25,
+        }
+    }
+
+    pub fn from_cents(cents: u32) -> Option<Coin> {
+        Coin::ALL.iter().copied().find(|coin| coin.value_in_cents() == cents)
+    }
+}
+
+// The coins to give: denomination (in cents) -> number of coins.
+pub type Breakdown = BTreeMap<u32, u32>;
+
+// Finds the smallest number of coins for the amount.
+//
+// For canonical coin systems (like the US coins) the greedy method,
+// taking the largest coin that fits, is always optimal and fast.
+// For other systems greedy can be wrong: with coins 1, 3 and 4 it gives
+// 4 + 1 + 1 for 6 instead of 3 + 3, so we use dynamic programming.
+#[derive(Debug, Clone)]
+pub struct ChangeMaker {
+    // Sorted from the largest to the smallest.
+    denominations: Vec<u32>,
+    canonical: bool,
+}
+
+impl ChangeMaker {
+    // Zero denominations are ignored.
+    pub fn new(denominations: &[u32]) -> ChangeMaker {
+        let mut denominations: Vec<u32> = denominations.iter().copied().filter(|&d| d > 0).collect();
+        denominations.sort_unstable_by(|a, b| b.cmp(a));
+        denominations.dedup();
+        let mut maker = ChangeMaker { denominations, canonical: false };
+        maker.canonical = maker.check_canonical();
+        maker
+    }
+
+    pub fn for_coins(coins: &[Coin]) -> ChangeMaker {
+        let denominations: Vec<u32> = coins.iter().map(|coin| coin.value_in_cents()).collect();
+        ChangeMaker::new(&denominations)
+    }
+
+    pub fn denominations(&self) -> &[u32] {
+        &self.denominations
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    // If greedy is not optimal for some amount, the smallest such amount
+    // is less than the sum of the two largest coins (Kozen and Zaks), so
+    // we only need to compare greedy and DP below that.
+    fn check_canonical(&self) -> bool {
+        let limit = match self.denominations.as_slice() {
+            [first, second, ..] => first + second,
+            _ => return true,
+        };
+        let optimal = min_coins_table(&self.denominations, limit);
+        (1..limit).all(|amount| {
+            let greedy = self.greedy(amount).map(|change| change.values().sum::<u32>());
+            greedy == optimal[amount as usize]
+        })
+    }
+
+    fn greedy(&self, mut amount: u32) -> Option<Breakdown> {
+        let mut change = Breakdown::new();
+        for &denomination in &self.denominations {
+            let count = amount / denomination;
+            if count > 0 {
+                change.insert(denomination, count);
+                amount -= count * denomination;
+            }
+        }
+        if amount == 0 {
+            Some(change)
+        } else {
+            None
+        }
+    }
+
+    // The optimal breakdown, `None` if the amount can't be made from
+    // the coins (for example, 3 cents from nickels and dimes).
+    //
+    // For non-canonical systems the DP table doesn't grow with the
+    // amount. An optimal breakdown has less than `largest` smaller coins:
+    // from `largest` coins we can always pick some with the sum divisible
+    // by `largest` and replace them with fewer largest coins. So the
+    // smaller coins sum to at most `(largest - 1) * second`, the table
+    // covers only that, and the rest of the amount is paid with the
+    // largest coins.
+    pub fn make_change(&self, amount: u32) -> Option<Breakdown> {
+        if self.canonical {
+            return self.greedy(amount);
+        }
+        let (largest, second) = match self.denominations.as_slice() {
+            [first, second, ..] => (*first, *second),
+            // One coin or none is always canonical.
+            _ => return self.greedy(amount),
+        };
+        let limit = (largest - 1).checked_mul(second).map_or(amount, |limit| limit.min(amount));
+        let table = min_coins_table(&self.denominations, limit.checked_add(1)?);
+        // The part paid with smaller coins: `amount - k * largest`.
+        let (rest, _) = (amount % largest..=limit)
+            .step_by(largest as usize)
+            .filter_map(|rest| table[rest as usize].map(|coins| (rest, coins + (amount - rest) / largest)))
+            .min_by_key(|&(_, coins)| coins)?;
+        let mut change = Breakdown::new();
+        if amount > rest {
+            change.insert(largest, (amount - rest) / largest);
+        }
+        // Walk back through the table: take the coin which leads to the
+        // amount with one coin less.
+        let mut rest = rest;
+        while rest > 0 {
+            let coins = table[rest as usize].expect("reachable amount");
+            let denomination = self.denominations.iter().copied()
+                .find(|&d| d <= rest && table[(rest - d) as usize] == Some(coins - 1))
+                .expect("optimal step exists");
+            *change.entry(denomination).or_insert(0) += 1;
+            rest -= denomination;
+        }
+        Some(change)
+    }
+
+    // The optimal breakdown using only the available coins
+    // (denomination -> count), `None` if the exact amount can't be paid.
+    //
+    // Greedy can fail here even for canonical systems: with no nickels,
+    // 30 cents are 3 dimes, not a quarter and 5 pennies (if there are no
+    // pennies either). So this is always done with dynamic programming,
+    // each available coin can be used once (0/1 knapsack). The table has
+    // `amount + 1` entries for each pack of coins, this is fine for the
+    // change in a vending machine, but not for arbitrary large amounts.
+    pub fn make_change_limited(&self, amount: u32, available: &Breakdown) -> Option<Breakdown> {
+        let amount = amount as usize;
+        // Group the coins of one denomination into packs of 1, 2, 4, ...
+        // coins, any count up to the available one is a sum of the packs,
+        // so we need log(count) items instead of count.
+        let mut packs: Vec<(u32, u32)> = Vec::new();
+        for &denomination in &self.denominations {
+            let mut count = available.get(&denomination).copied().unwrap_or(0);
+            let mut size = 1;
+            while count > 0 {
+                let pack = size.min(count);
+                packs.push((denomination, pack));
+                count -= pack;
+                size *= 2;
+            }
+        }
+        // best[a] is the smallest number of coins for `a`, `taken[i][a]`
+        // is set if the pack `i` is used in the best solution for `a`
+        // with the packs `0..=i`.
+        let mut best: Vec<Option<u32>> = vec![None; amount + 1];
+        best[0] = Some(0);
+        let mut taken = vec![vec![false; amount + 1]; packs.len()];
+        for (index, &(denomination, count)) in packs.iter().enumerate() {
+            let value = (denomination * count) as usize;
+            for a in (value..=amount).rev() {
+                if let Some(coins) = best[a - value] {
+                    if best[a].is_none_or(|current| coins + count < current) {
+                        best[a] = Some(coins + count);
+                        taken[index][a] = true;
+                    }
+                }
+            }
+        }
+        best[amount]?;
+        let mut change = Breakdown::new();
+        let mut rest = amount;
+        for (index, &(denomination, count)) in packs.iter().enumerate().rev() {
+            if taken[index][rest] {
+                *change.entry(denomination).or_insert(0) += count;
+                rest -= (denomination * count) as usize;
+            }
+        }
+        Some(change)
+    }
+}
+
+// The smallest number of coins for each amount in `0..limit`, `None`
+// for the amounts that can't be made.
+fn min_coins_table(denominations: &[u32], limit: u32) -> Vec<Option<u32>> {
+    let mut table: Vec<Option<u32>> = vec![None; limit as usize];
+    if let Some(first) = table.first_mut() {
+        *first = Some(0);
+    }
+    for amount in 1..limit {
+        table[amount as usize] = denominations.iter()
+            .filter(|&&d| d <= amount)
+            .filter_map(|&d| table[(amount - d) as usize])
+            .min()
+            .map(|coins| coins + 1);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coins(change: &Breakdown) -> u32 {
+        change.values().sum()
+    }
+
+    fn value(change: &Breakdown) -> u32 {
+        change.iter().map(|(denomination, count)| denomination * count).sum()
+    }
+
+    #[test]
+    fn detects_canonical_systems() {
+        assert!(ChangeMaker::for_coins(&Coin::ALL).is_canonical());
+        assert!(ChangeMaker::new(&[1, 2, 5, 10, 20, 50]).is_canonical());
+        assert!(ChangeMaker::new(&[5]).is_canonical());
+        assert!(ChangeMaker::new(&[]).is_canonical());
+        assert!(!ChangeMaker::new(&[1, 3, 4]).is_canonical());
+        // Without nickels: 30 is 3 dimes, greedy gives 25 + 5 * 1.
+        assert!(!ChangeMaker::new(&[1, 10, 25]).is_canonical());
+        assert_eq!(ChangeMaker::new(&[4, 0, 1, 3, 4]).denominations(), &[4, 3, 1]);
+    }
+
+    #[test]
+    fn canonical_uses_largest_coins() {
+        let maker = ChangeMaker::for_coins(&Coin::ALL);
+        let change = maker.make_change(68).unwrap();
+        assert_eq!(change, Breakdown::from([(25, 2), (10, 1), (5, 1), (1, 3)]));
+        assert_eq!(maker.make_change(0), Some(Breakdown::new()));
+        assert_eq!(maker.make_change(u32::MAX).map(|change| value(&change)), Some(u32::MAX));
+        assert_eq!(ChangeMaker::new(&[5, 10]).make_change(3), None);
+    }
+
+    #[test]
+    fn non_canonical_finds_fewest_coins() {
+        let maker = ChangeMaker::new(&[1, 3, 4]);
+        assert_eq!(maker.make_change(6), Some(Breakdown::from([(3, 2)])));
+        let maker = ChangeMaker::new(&[1, 10, 25]);
+        assert_eq!(maker.make_change(30), Some(Breakdown::from([(10, 3)])));
+        assert_eq!(maker.make_change(0), Some(Breakdown::new()));
+        assert_eq!(ChangeMaker::new(&[4, 6, 9]).make_change(7), None);
+    }
+
+    #[test]
+    fn non_canonical_matches_full_table() {
+        for denominations in [&[1, 3, 4][..], &[1, 10, 25], &[4, 6, 9], &[7, 12, 13, 30]] {
+            let maker = ChangeMaker::new(denominations);
+            assert!(!maker.is_canonical());
+            let table = min_coins_table(maker.denominations(), 500);
+            for amount in 0..500 {
+                let change = maker.make_change(amount);
+                assert_eq!(change.as_ref().map(coins), table[amount as usize], "{:?} {}", denominations, amount);
+                if let Some(change) = change {
+                    assert_eq!(value(&change), amount);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn non_canonical_handles_large_amounts() {
+        // The table doesn't depend on the amount, `amount + 1` doesn't
+        // overflow.
+        let maker = ChangeMaker::new(&[1, 3, 4]);
+        let change = maker.make_change(u32::MAX).unwrap();
+        assert_eq!(value(&change), u32::MAX);
+        assert_eq!(coins(&change), u32::MAX / 4 + 1);
+        assert_eq!(ChangeMaker::new(&[4, 6]).make_change(u32::MAX), None);
+    }
+
+    #[test]
+    fn limited_change_without_some_coins() {
+        let maker = ChangeMaker::for_coins(&Coin::ALL);
+        // No nickels and no pennies: 30 is three dimes.
+        let available = Breakdown::from([(25, 10), (10, 3)]);
+        assert_eq!(maker.make_change_limited(30, &available), Some(Breakdown::from([(10, 3)])));
+        // Only two dimes left.
+        let available = Breakdown::from([(25, 10), (10, 2)]);
+        assert_eq!(maker.make_change_limited(30, &available), None);
+        assert_eq!(maker.make_change_limited(45, &available), Some(Breakdown::from([(25, 1), (10, 2)])));
+        // Coins that are not in the system are ignored.
+        let available = Breakdown::from([(2, 10)]);
+        assert_eq!(maker.make_change_limited(4, &available), None);
+        assert_eq!(maker.make_change_limited(0, &Breakdown::new()), Some(Breakdown::new()));
+        // Many coins of one denomination are used in packs.
+        let available = Breakdown::from([(1, 1000), (5, 3)]);
+        assert_eq!(maker.make_change_limited(117, &available), Some(Breakdown::from([(5, 3), (1, 102)])));
+    }
+}
@@ -0,0 +1,430 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ip::{AddrParseError, IpAddr};
+
+// Network in the CIDR notation, like `10.0.0.0/8`: the address and the
+// prefix length, the number of the leading bits that are the same for
+// all addresses in the network.
+//
+// The address is always the network address (host bits are zero):
+// `Network::new` and `parse` clear the host bits, so `10.1.2.3/8` is the
+// same network as `10.0.0.0/8`.
+//
+// The calculations are done with `u128` numbers, which fit both IPv4
+// (32 bits) and IPv6 (128 bits) addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CidrError {
+    Address(AddrParseError),
+    // The `/prefix` part is missing.
+    MissingPrefix,
+    InvalidPrefix(String),
+    // The prefix is longer than the address: 32 bits for IPv4 and 128
+    // bits for IPv6.
+    PrefixTooLong { prefix: u8, max: u8 },
+}
+
+impl fmt::Display for CidrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CidrError::Address(err) => write!(f, "invalid address: {}", err),
+            CidrError::MissingPrefix => write!(f, "prefix length is missing, expected address/prefix"),
+            CidrError::InvalidPrefix(prefix) => write!(f, "invalid prefix length '{}'", prefix),
+            CidrError::PrefixTooLong { prefix, max } => {
+                write!(f, "prefix length {} is too long, the maximum is {}", prefix, max)
+            }
+        }
+    }
+}
+
+impl error::Error for CidrError {}
+
+impl From<AddrParseError> for CidrError {
+    fn from(err: AddrParseError) -> CidrError {
+        CidrError::Address(err)
+    }
+}
+
+// The number of bits in the address.
+fn width(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(..) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn to_bits(addr: &IpAddr) -> u128 {
+    match *addr {
+        IpAddr::V4(a, b, c, d) => u128::from(u32::from_be_bytes([a, b, c, d])),
+        IpAddr::V6(groups) => groups.iter().fold(0, |bits, &group| bits << 16 | u128::from(group)),
+    }
+}
+
+// Convert bits back to the address of the same kind as `like`.
+fn from_bits(bits: u128, like: &IpAddr) -> IpAddr {
+    match like {
+        IpAddr::V4(..) => {
+            let [a, b, c, d] = (bits as u32).to_be_bytes();
+            IpAddr::V4(a, b, c, d)
+        }
+        IpAddr::V6(_) => {
+            let mut groups = [0; 8];
+            for (index, group) in groups.iter_mut().enumerate() {
+                *group = (bits >> (16 * (7 - index))) as u16;
+            }
+            IpAddr::V6(groups)
+        }
+    }
+}
+
+// The mask with the host bits set: `0.0.0.255` for `/24`.
+// Shifting `u128` by 128 bits overflows, so we use `checked_shr`.
+fn host_mask(prefix: u8, width: u8) -> u128 {
+    (u128::MAX >> (128 - width)).checked_shr(u32::from(prefix)).unwrap_or(0)
+}
+
+impl Network {
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Network, CidrError> {
+        let max = width(&addr);
+        if prefix > max {
+            return Err(CidrError::PrefixTooLong { prefix, max });
+        }
+        let bits = to_bits(&addr) & !host_mask(prefix, max);
+        Ok(Network { addr: from_bits(bits, &addr), prefix })
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.addr.is_ipv4()
+    }
+
+    fn width(&self) -> u8 {
+        width(&self.addr)
+    }
+
+    fn first_bits(&self) -> u128 {
+        to_bits(&self.addr)
+    }
+
+    fn last_bits(&self) -> u128 {
+        self.first_bits() | host_mask(self.prefix, self.width())
+    }
+
+    // The first address in the network.
+    pub fn network(&self) -> IpAddr {
+        self.addr
+    }
+
+    // The last address in the network, for IPv4 it is the broadcast
+    // address (IPv6 has no broadcast, but the last address is still
+    // useful for planning).
+    pub fn broadcast(&self) -> IpAddr {
+        from_bits(self.last_bits(), &self.addr)
+    }
+
+    // The network mask: `255.255.255.0` for `/24`.
+    pub fn netmask(&self) -> IpAddr {
+        let width = self.width();
+        from_bits(host_mask(0, width) & !host_mask(self.prefix, width), &self.addr)
+    }
+
+    // The number of addresses in the network, `::/0` has 2^128 addresses
+    // which doesn't fit into `u128`, so the result saturates at
+    // `u128::MAX`.
+    pub fn size(&self) -> u128 {
+        host_mask(self.prefix, self.width()).saturating_add(1)
+    }
+
+    // The range of addresses that can be assigned to hosts.
+    // For IPv4 the network and broadcast addresses are excluded, except
+    // for `/31` (point-to-point links, RFC 3021) and `/32` (single host).
+    // For IPv6 all addresses can be used.
+    pub fn host_range(&self) -> (IpAddr, IpAddr) {
+        let (mut first, mut last) = (self.first_bits(), self.last_bits());
+        if self.is_ipv4() && self.prefix < 31 {
+            first += 1;
+            last -= 1;
+        }
+        (from_bits(first, &self.addr), from_bits(last, &self.addr))
+    }
+
+    pub fn hosts(&self) -> Hosts {
+        let (first, last) = self.host_range();
+        Hosts { next: Some(to_bits(&first)), last: to_bits(&last), like: self.addr }
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        addr.is_ipv4() == self.is_ipv4() && self.first_bits() <= to_bits(addr) && to_bits(addr) <= self.last_bits()
+    }
+
+    // The `other` network is completely inside of this one.
+    pub fn contains_network(&self, other: &Network) -> bool {
+        self.prefix <= other.prefix && self.contains(&other.addr)
+    }
+
+    pub fn overlaps(&self, other: &Network) -> bool {
+        self.contains_network(other) || other.contains_network(self)
+    }
+
+    // Split the network into subnets with the longer `prefix`, `None` if
+    // the prefix is shorter than the network prefix or too long for the
+    // address.
+    pub fn subnets(&self, prefix: u8) -> Option<Subnets> {
+        if prefix < self.prefix || prefix > self.width() {
+            return None;
+        }
+        Some(Subnets {
+            next: Some(self.first_bits()),
+            last: self.last_bits(),
+            step: host_mask(prefix, self.width()),
+            prefix,
+            like: self.addr,
+        })
+    }
+
+    // The network one bit shorter that contains this one, `None` for `/0`.
+    pub fn supernet(&self) -> Option<Network> {
+        match self.prefix {
+            0 => None,
+            prefix => Network::new(self.addr, prefix - 1).ok(),
+        }
+    }
+}
+
+// Iterator over the host addresses, see `Network::hosts`.
+pub struct Hosts {
+    next: Option<u128>,
+    last: u128,
+    like: IpAddr,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let bits = self.next?;
+        // `None` after the last address, this way we don't overflow on
+        // the last address of the address space.
+        self.next = if bits < self.last { Some(bits + 1) } else { None };
+        Some(from_bits(bits, &self.like))
+    }
+}
+
+// Iterator over the subnets, see `Network::subnets`.
+pub struct Subnets {
+    next: Option<u128>,
+    last: u128,
+    // Host mask of the subnet, the subnet size is `step + 1`.
+    step: u128,
+    prefix: u8,
+    like: IpAddr,
+}
+
+impl Iterator for Subnets {
+    type Item = Network;
+
+    fn next(&mut self) -> Option<Network> {
+        let bits = self.next?;
+        let subnet_last = bits | self.step;
+        self.next = if subnet_last < self.last { Some(subnet_last + 1) } else { None };
+        Some(Network { addr: from_bits(bits, &self.like), prefix: self.prefix })
+    }
+}
+
+// Merge the networks into the smallest list of networks covering the
+// same addresses: networks inside of other networks are removed and
+// neighbour networks are joined, `10.0.0.0/25` and `10.0.0.128/25` become
+// `10.0.0.0/24`.
+pub fn aggregate(networks: &[Network]) -> Vec<Network> {
+    let mut result: Vec<Network> = networks.to_vec();
+    result.sort();
+    loop {
+        let mut merged: Vec<Network> = Vec::new();
+        for network in result.iter() {
+            if let Some(last) = merged.last_mut() {
+                if last.contains_network(network) {
+                    continue;
+                }
+                // Two halves of the same supernet.
+                if let Some(parent) = last.supernet() {
+                    if last.prefix == network.prefix && parent.contains_network(network) {
+                        *last = parent;
+                        continue;
+                    }
+                }
+            }
+            merged.push(*network);
+        }
+        // Joining networks can make new neighbours, repeat until nothing
+        // changes.
+        if merged.len() == result.len() {
+            return merged;
+        }
+        merged.sort();
+        result = merged;
+    }
+}
+
+impl FromStr for Network {
+    type Err = CidrError;
+
+    fn from_str(text: &str) -> Result<Network, CidrError> {
+        let text = text.trim();
+        let (addr, prefix) = text.split_once('/').ok_or(CidrError::MissingPrefix)?;
+        let addr: IpAddr = addr.parse()?;
+        let is_valid = !prefix.is_empty() && prefix.bytes().all(|b| b.is_ascii_digit());
+        let prefix = match prefix.parse() {
+            Ok(prefix) if is_valid => prefix,
+            _ => return Err(CidrError::InvalidPrefix(prefix.to_string())),
+        };
+        Network::new(addr, prefix)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(text: &str) -> Network {
+        text.parse().unwrap()
+    }
+
+    fn addr(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    fn nets(texts: &[&str]) -> Vec<Network> {
+        texts.iter().map(|text| net(text)).collect()
+    }
+
+    #[test]
+    fn new_clears_host_bits() {
+        let network = Network::new(addr("10.1.2.3"), 8).unwrap();
+        assert_eq!(network.network(), addr("10.0.0.0"));
+        assert_eq!(network, net("10.0.0.0/8"));
+        assert_eq!(net("10.1.2.3/8").to_string(), "10.0.0.0/8");
+        assert_eq!(net("192.168.1.255/23").to_string(), "192.168.0.0/23");
+        assert_eq!(net("2001:db8::ffff/64").to_string(), "2001:db8::/64");
+    }
+
+    #[test]
+    fn parses_and_checks_prefix() {
+        assert_eq!("10.0.0.0".parse::<Network>(), Err(CidrError::MissingPrefix));
+        assert_eq!("10.0.0.0/".parse::<Network>(), Err(CidrError::InvalidPrefix("".to_string())));
+        assert_eq!("10.0.0.0/+8".parse::<Network>(), Err(CidrError::InvalidPrefix("+8".to_string())));
+        assert_eq!("10.0.0.0/256".parse::<Network>(), Err(CidrError::InvalidPrefix("256".to_string())));
+        assert_eq!("10.0.0.0/33".parse::<Network>(), Err(CidrError::PrefixTooLong { prefix: 33, max: 32 }));
+        assert_eq!("::/129".parse::<Network>(), Err(CidrError::PrefixTooLong { prefix: 129, max: 128 }));
+        assert_eq!("10.0.0/8".parse::<Network>(), Err(CidrError::Address(AddrParseError::OctetCount(3))));
+    }
+
+    #[test]
+    fn whole_address_space() {
+        let all = net("10.1.2.3/0");
+        assert_eq!(all.to_string(), "0.0.0.0/0");
+        assert_eq!(all.netmask(), addr("0.0.0.0"));
+        assert_eq!(all.broadcast(), addr("255.255.255.255"));
+        assert_eq!(all.size(), 1 << 32);
+        assert_eq!(all.host_range(), (addr("0.0.0.1"), addr("255.255.255.254")));
+        assert!(all.contains(&addr("255.255.255.255")));
+        assert!(!all.contains(&addr("::1")));
+        assert_eq!(all.supernet(), None);
+
+        let all = net("::/0");
+        assert_eq!(all.broadcast(), addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"));
+        // 2^128 addresses, saturated.
+        assert_eq!(all.size(), u128::MAX);
+        assert_eq!(all.host_range(), (addr("::"), addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(!all.contains(&addr("10.0.0.1")));
+    }
+
+    #[test]
+    fn single_address_networks() {
+        let host = net("10.0.0.5/32");
+        assert_eq!(host.netmask(), addr("255.255.255.255"));
+        assert_eq!(host.broadcast(), addr("10.0.0.5"));
+        assert_eq!(host.size(), 1);
+        assert_eq!(host.host_range(), (addr("10.0.0.5"), addr("10.0.0.5")));
+        assert_eq!(host.hosts().collect::<Vec<_>>(), vec![addr("10.0.0.5")]);
+        assert_eq!(host.supernet(), Some(net("10.0.0.4/31")));
+
+        let host = net("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff/128");
+        assert_eq!(host.size(), 1);
+        assert_eq!(host.netmask(), addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"));
+        // The last address of the address space doesn't overflow.
+        assert_eq!(host.hosts().count(), 1);
+        assert_eq!(host.subnets(128).unwrap().collect::<Vec<_>>(), vec![host]);
+        assert!(host.subnets(127).is_none());
+    }
+
+    #[test]
+    fn host_ranges() {
+        let network = net("192.168.1.0/24");
+        assert_eq!(network.netmask(), addr("255.255.255.0"));
+        assert_eq!(network.broadcast(), addr("192.168.1.255"));
+        assert_eq!(network.size(), 256);
+        assert_eq!(network.hosts().count(), 254);
+        // Point-to-point links use both addresses.
+        assert_eq!(net("10.0.0.0/31").hosts().collect::<Vec<_>>(), vec![addr("10.0.0.0"), addr("10.0.0.1")]);
+        assert_eq!(net("2001:db8::/126").hosts().count(), 4);
+    }
+
+    #[test]
+    fn splits_into_subnets() {
+        let subnets: Vec<Network> = net("10.0.0.0/24").subnets(26).unwrap().collect();
+        assert_eq!(subnets, nets(&["10.0.0.0/26", "10.0.0.64/26", "10.0.0.128/26", "10.0.0.192/26"]));
+        assert!(net("10.0.0.0/24").subnets(23).is_none());
+        assert!(net("10.0.0.0/24").subnets(33).is_none());
+        assert!(net("10.0.0.0/8").contains_network(&net("10.20.0.0/16")));
+        assert!(!net("10.20.0.0/16").contains_network(&net("10.0.0.0/8")));
+        assert!(net("10.20.0.0/16").overlaps(&net("10.0.0.0/8")));
+        assert!(!net("10.0.0.0/25").overlaps(&net("10.0.0.128/25")));
+    }
+
+    #[test]
+    fn aggregates_adjacent_networks() {
+        assert_eq!(aggregate(&nets(&["10.0.0.128/25", "10.0.0.0/25"])), nets(&["10.0.0.0/24"]));
+        // Joined networks are joined again with their neighbours.
+        let quarters = nets(&["10.0.0.0/26", "10.0.0.64/26", "10.0.0.128/26", "10.0.0.192/26"]);
+        assert_eq!(aggregate(&quarters), nets(&["10.0.0.0/24"]));
+        assert_eq!(aggregate(&nets(&["10.0.0.0/25", "10.0.0.128/26", "10.0.0.192/26"])), nets(&["10.0.0.0/24"]));
+        assert_eq!(aggregate(&nets(&["2001:db8::/33", "2001:db8:8000::/33"])), nets(&["2001:db8::/32"]));
+    }
+
+    #[test]
+    fn keeps_neighbours_of_different_supernets() {
+        // Adjacent, but `10.0.0.128/25` and `10.0.1.0/25` are halves of
+        // different `/24` networks.
+        let networks = nets(&["10.0.0.128/25", "10.0.1.0/25"]);
+        assert_eq!(aggregate(&networks), networks);
+        // Different sizes are not joined either.
+        let networks = nets(&["10.0.0.0/25", "10.0.0.128/26"]);
+        assert_eq!(aggregate(&networks), networks);
+    }
+
+    #[test]
+    fn aggregates_nested_networks() {
+        let networks = nets(&["10.1.0.0/16", "10.0.0.0/8", "10.1.2.0/24", "10.0.0.0/8", "192.168.0.0/16"]);
+        assert_eq!(aggregate(&networks), nets(&["10.0.0.0/8", "192.168.0.0/16"]));
+        // IPv4 and IPv6 networks are never joined.
+        let networks = nets(&["0.0.0.0/1", "128.0.0.0/1", "::/1", "8000::/1", "::/64"]);
+        assert_eq!(aggregate(&networks), nets(&["0.0.0.0/0", "::/0"]));
+        assert_eq!(aggregate(&[]), Vec::new());
+    }
+}
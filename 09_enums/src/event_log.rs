@@ -0,0 +1,318 @@
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::message::{Message, MessageHandler};
+
+// Event sourcing for the messages: the log keeps every dispatched
+// message and the state is never stored directly, it is rebuilt by
+// applying (replaying) the messages from the log.
+//
+// The log is append-only, events get increasing sequence numbers
+// starting from 1. A snapshot stores the state after some event, so we
+// don't have to replay the whole log: replay from the snapshot applies
+// only the events after it.
+//
+// One log can hold several sessions (the REPL appends to the saved log):
+// `Quit` ends the session and the next message starts a new one, which
+// continues from the state the previous session left.
+
+// Errors when loading the saved log.
+#[derive(Debug)]
+pub enum LogError {
+    // Sequence numbers should increase, holds the wrong one.
+    OutOfOrder(u64),
+    Json(serde_json::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogError::OutOfOrder(seq) => write!(f, "event {} is out of order", seq),
+            LogError::Json(err) => write!(f, "invalid event: {}", err),
+            LogError::Io(err) => write!(f, "failed to read the log: {}", err),
+        }
+    }
+}
+
+impl error::Error for LogError {}
+
+impl From<io::Error> for LogError {
+    fn from(err: io::Error) -> LogError {
+        LogError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LogError {
+    fn from(err: serde_json::Error) -> LogError {
+        LogError::Json(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub seq: u64,
+    // Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub message: Message,
+}
+
+// The state of the drawing program, built from the messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    pub position: (i32, i32),
+    pub color: (i32, i32, i32),
+    // Text from all `Write` messages.
+    pub text: String,
+    // Set by `Quit` at the end of a session, cleared by the first
+    // message of the next session.
+    pub terminated: bool,
+}
+
+impl State {
+    pub fn apply(&mut self, message: &Message) {
+        self.terminated = false;
+        match message {
+            Message::Quit => self.terminated = true,
+            Message::Move { x, y } => self.position = (*x, *y),
+            Message::Write(text) => self.text.push_str(text),
+            Message::ChangeColor(r, g, b) => self.color = (*r, *g, *b),
+        }
+    }
+}
+
+// The state can be updated by the `Dispatcher` directly.
+impl MessageHandler for State {
+    fn handle(&mut self, message: &Message) {
+        self.apply(message);
+    }
+}
+
+// The state after the event `seq` (0 means no events applied).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub seq: u64,
+    pub state: State,
+}
+
+#[derive(Debug, Default)]
+pub struct EventLog {
+    events: Vec<Event>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_millis() as u64).unwrap_or(0)
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    // The sequence number of the last event, 0 for the empty log.
+    pub fn last_seq(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.seq)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    // Append the message with the current time, returns its sequence
+    // number.
+    pub fn append(&mut self, message: Message) -> u64 {
+        self.append_at(message, now_ms())
+    }
+
+    // Append the message with the given time (useful for simulations
+    // and imports, where the time doesn't come from the clock).
+    pub fn append_at(&mut self, message: Message, timestamp_ms: u64) -> u64 {
+        let seq = self.last_seq() + 1;
+        self.events.push(Event { seq, timestamp_ms, message });
+        seq
+    }
+
+    // Events after `seq`: `events_after(0)` returns all events.
+    pub fn events_after(&self, seq: u64) -> &[Event] {
+        // Sequence numbers are increasing, so we can use binary search.
+        let start = self.events.partition_point(|event| event.seq <= seq);
+        &self.events[start..]
+    }
+
+    // Rebuild the state from all events.
+    pub fn replay(&self) -> State {
+        self.replay_from(&Snapshot { seq: 0, state: State::default() })
+    }
+
+    // Rebuild the state from the snapshot and the events after it.
+    pub fn replay_from(&self, snapshot: &Snapshot) -> State {
+        let mut state = snapshot.state.clone();
+        for event in self.events_after(snapshot.seq) {
+            state.apply(&event.message);
+        }
+        state
+    }
+
+    // The state after the event `seq`, later events are ignored.
+    pub fn replay_until(&self, seq: u64) -> State {
+        let mut state = State::default();
+        for event in self.events.iter().take_while(|event| event.seq <= seq) {
+            state.apply(&event.message);
+        }
+        state
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot { seq: self.last_seq(), state: self.replay() }
+    }
+
+    // Append the message and write its event to `writer` right away (as
+    // a JSON line), so the saved log stays up to date after each message.
+    pub fn record<W: Write>(&mut self, message: Message, writer: &mut W) -> io::Result<u64> {
+        let seq = self.append(message);
+        self.write_after(seq - 1, writer)?;
+        writer.flush()?;
+        Ok(seq)
+    }
+
+    // Save the log as JSON lines, one event per line.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_after(0, writer)
+    }
+
+    // Save only the events after `seq`, to append them to the saved log.
+    pub fn write_after<W: Write>(&self, seq: u64, writer: &mut W) -> io::Result<()> {
+        for event in self.events_after(seq) {
+            serde_json::to_writer(&mut *writer, event)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    // Load the log saved by `write_to`.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<EventLog, LogError> {
+        let mut log = EventLog::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Event = serde_json::from_str(&line)?;
+            // Keep the log append-only: sequence numbers should grow.
+            if event.seq <= log.last_seq() {
+                return Err(LogError::OutOfOrder(event.seq));
+            }
+            log.events.push(event);
+        }
+        Ok(log)
+    }
+}
+
+// Register the log in the `Dispatcher` to record all dispatched
+// messages.
+impl MessageHandler for EventLog {
+    fn handle(&mut self, message: &Message) {
+        self.append(message.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::Move { x: 10, y: 100 },
+            Message::ChangeColor(255, 0, 0),
+            Message::Write("hello".to_string()),
+            Message::Quit,
+        ]
+    }
+
+    #[test]
+    fn replay_builds_state() {
+        let mut log = EventLog::new();
+        for message in messages() {
+            log.append(message);
+        }
+        assert_eq!(log.last_seq(), 4);
+        let state = log.replay();
+        assert_eq!(state.position, (10, 100));
+        assert_eq!(state.color, (255, 0, 0));
+        assert_eq!(state.text, "hello");
+        assert!(state.terminated);
+
+        let snapshot = Snapshot { seq: 2, state: log.replay_until(2) };
+        assert_eq!(snapshot.state.text, "");
+        assert_eq!(log.replay_from(&snapshot), state);
+        assert_eq!(log.events_after(3).len(), 1);
+    }
+
+    #[test]
+    fn replays_several_sessions() {
+        // The first session is saved, the second one is appended to it.
+        let mut first = EventLog::new();
+        let mut saved = Vec::new();
+        for message in messages() {
+            first.record(message, &mut saved).unwrap();
+        }
+        let mut second = EventLog::read_from(saved.as_slice()).unwrap();
+        let messages = vec![Message::Write(", world".to_string()), Message::Move { x: 1, y: 2 }, Message::Quit];
+        for message in messages {
+            second.record(message, &mut saved).unwrap();
+        }
+
+        let log = EventLog::read_from(saved.as_slice()).unwrap();
+        assert_eq!(log.last_seq(), 7);
+        let state = log.replay();
+        assert_eq!(state.text, "hello, world");
+        assert_eq!(state.position, (1, 2));
+        assert_eq!(state.color, (255, 0, 0));
+        assert!(state.terminated);
+
+        // Between the sessions.
+        assert!(log.replay_until(4).terminated);
+        let state = log.replay_until(5);
+        assert!(!state.terminated);
+        assert_eq!(state.text, "hello, world");
+        assert_eq!(log.replay_from(&Snapshot { seq: 4, state: log.replay_until(4) }), log.replay());
+    }
+
+    #[test]
+    fn record_writes_each_event() {
+        let mut log = EventLog::new();
+        let mut saved = Vec::new();
+        for (index, message) in messages().into_iter().enumerate() {
+            let seq = log.record(message, &mut saved).unwrap();
+            assert_eq!(seq, index as u64 + 1);
+            // The saved log is complete after every message.
+            let loaded = EventLog::read_from(saved.as_slice()).unwrap();
+            assert_eq!(loaded.events(), log.events());
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_order_events() {
+        let mut log = EventLog::new();
+        log.append_at(Message::Quit, 1);
+        log.append_at(Message::Quit, 2);
+        let mut saved = Vec::new();
+        log.write_to(&mut saved).unwrap();
+        // Save the same events again, their sequence numbers repeat.
+        log.write_to(&mut saved).unwrap();
+        assert!(matches!(EventLog::read_from(saved.as_slice()), Err(LogError::OutOfOrder(1))));
+        assert!(matches!(EventLog::read_from(&b"{"[..]), Err(LogError::Json(_))));
+    }
+}
@@ -0,0 +1,366 @@
+use std::error;
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+// IP address, like the `IpAddr2` enum in `src/main.rs`, but with typed
+// data for both variants: four octets for IPv4 and eight 16-bit groups
+// for IPv6.
+//
+// Addresses are parsed with `"10.0.0.1".parse::<IpAddr>()` and formatted
+// with `Display`, IPv6 in the canonical form from RFC 5952:
+// lowercase hex digits without leading zeros and the longest run of zero
+// groups replaced by `::`, like `2001:db8::1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddr {
+    V4(u8, u8, u8, u8),
+    V6([u16; 8]),
+}
+
+// Everything that can be wrong in the address text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddrParseError {
+    Empty,
+    // IPv4 address should have exactly 4 octets, holds the number found.
+    OctetCount(usize),
+    // Not a decimal number 0..=255 (or has leading zeros, like `010`,
+    // which some tools read as octal).
+    InvalidOctet(String),
+    // IPv6 address should have 8 groups (less if `::` is used), holds
+    // the number found.
+    GroupCount(usize),
+    // Not a 1 to 4 digit hex number.
+    InvalidGroup(String),
+    // `::` can be used only once in the address.
+    MultipleCompression,
+    // Embedded IPv4 address (like in `::ffff:10.0.0.1`) can only be at
+    // the end.
+    MisplacedIpv4,
+}
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddrParseError::Empty => write!(f, "empty address"),
+            AddrParseError::OctetCount(count) => write!(f, "IPv4 address should have 4 octets, found {}", count),
+            AddrParseError::InvalidOctet(octet) => write!(f, "invalid IPv4 octet '{}'", octet),
+            AddrParseError::GroupCount(count) => write!(f, "IPv6 address should have 8 groups, found {}", count),
+            AddrParseError::InvalidGroup(group) => write!(f, "invalid IPv6 group '{}'", group),
+            AddrParseError::MultipleCompression => write!(f, "'::' can be used only once"),
+            AddrParseError::MisplacedIpv4 => write!(f, "embedded IPv4 address should be at the end"),
+        }
+    }
+}
+
+impl error::Error for AddrParseError {}
+
+impl IpAddr {
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self, IpAddr::V4(..))
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        match *self {
+            IpAddr::V4(a, ..) => a == 127,
+            IpAddr::V6(groups) => groups == [0, 0, 0, 0, 0, 0, 0, 1],
+        }
+    }
+
+    // The IPv4 address from the IPv4-mapped IPv6 address
+    // (`::ffff:a.b.c.d`), `None` for other addresses.
+    pub fn to_ipv4_mapped(&self) -> Option<IpAddr> {
+        match *self {
+            IpAddr::V6([0, 0, 0, 0, 0, 0xffff, high, low]) => {
+                Some(IpAddr::V4((high >> 8) as u8, high as u8, (low >> 8) as u8, low as u8))
+            }
+            _ => None,
+        }
+    }
+
+    // The IPv4-mapped IPv6 address for IPv4, the same address for IPv6.
+    pub fn to_ipv6(&self) -> IpAddr {
+        match *self {
+            IpAddr::V4(a, b, c, d) => IpAddr::V6([
+                0, 0, 0, 0, 0, 0xffff,
+                u16::from(a) << 8 | u16::from(b),
+                u16::from(c) << 8 | u16::from(d),
+            ]),
+            IpAddr::V6(_) => *self,
+        }
+    }
+}
+
+fn parse_ipv4(text: &str) -> Result<[u8; 4], AddrParseError> {
+    let parts: Vec<&str> = text.split('.').collect();
+    if parts.len() != 4 {
+        return Err(AddrParseError::OctetCount(parts.len()));
+    }
+    let mut octets = [0; 4];
+    for (octet, part) in octets.iter_mut().zip(parts) {
+        let is_valid = !part.is_empty()
+            && part.len() <= 3
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && !(part.len() > 1 && part.starts_with('0'));
+        *octet = match part.parse() {
+            Ok(value) if is_valid => value,
+            _ => return Err(AddrParseError::InvalidOctet(part.to_string())),
+        };
+    }
+    Ok(octets)
+}
+
+// Parse the groups on one side of `::` (or the whole address if there is
+// no `::`), the IPv4 address is allowed in the last group if `is_last`.
+fn parse_groups(text: &str, is_last: bool) -> Result<Vec<u16>, AddrParseError> {
+    let mut groups = Vec::new();
+    if text.is_empty() {
+        return Ok(groups);
+    }
+    let parts: Vec<&str> = text.split(':').collect();
+    for (index, part) in parts.iter().enumerate() {
+        if part.contains('.') {
+            if !is_last || index != parts.len() - 1 {
+                return Err(AddrParseError::MisplacedIpv4);
+            }
+            let [a, b, c, d] = parse_ipv4(part)?;
+            groups.push(u16::from(a) << 8 | u16::from(b));
+            groups.push(u16::from(c) << 8 | u16::from(d));
+            continue;
+        }
+        let is_valid = !part.is_empty() && part.len() <= 4 && part.bytes().all(|b| b.is_ascii_hexdigit());
+        match u16::from_str_radix(part, 16) {
+            Ok(group) if is_valid => groups.push(group),
+            _ => return Err(AddrParseError::InvalidGroup(part.to_string())),
+        }
+    }
+    Ok(groups)
+}
+
+fn parse_ipv6(text: &str) -> Result<[u16; 8], AddrParseError> {
+    let halves: Vec<&str> = text.split("::").collect();
+    let groups = match halves.as_slice() {
+        [all] => {
+            let groups = parse_groups(all, true)?;
+            if groups.len() != 8 {
+                return Err(AddrParseError::GroupCount(groups.len()));
+            }
+            groups
+        }
+        [head, tail] => {
+            let head = parse_groups(head, false)?;
+            let tail = parse_groups(tail, true)?;
+            // `::` replaces at least one zero group.
+            let count = head.len() + tail.len();
+            if count > 7 {
+                return Err(AddrParseError::GroupCount(count));
+            }
+            let mut groups = head;
+            groups.resize(8 - tail.len(), 0);
+            groups.extend(tail);
+            groups
+        }
+        _ => return Err(AddrParseError::MultipleCompression),
+    };
+    let mut result = [0; 8];
+    result.copy_from_slice(&groups);
+    Ok(result)
+}
+
+impl FromStr for IpAddr {
+    type Err = AddrParseError;
+
+    fn from_str(text: &str) -> Result<IpAddr, AddrParseError> {
+        let text = text.trim();
+        if text.is_empty() {
+            Err(AddrParseError::Empty)
+        } else if text.contains(':') {
+            Ok(IpAddr::V6(parse_ipv6(text)?))
+        } else {
+            let [a, b, c, d] = parse_ipv4(text)?;
+            Ok(IpAddr::V4(a, b, c, d))
+        }
+    }
+}
+
+// The longest run of zero groups (at least two groups long), the first
+// one if there are several runs of the same length.
+// Returns the start and the length of the run.
+fn longest_zero_run(groups: &[u16; 8]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = 0;
+    while start < groups.len() {
+        if groups[start] != 0 {
+            start += 1;
+            continue;
+        }
+        let len = groups[start..].iter().take_while(|&&group| group == 0).count();
+        if len >= 2 && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((start, len));
+        }
+        start += len;
+    }
+    best
+}
+
+fn write_groups(f: &mut fmt::Formatter, groups: &[u16]) -> fmt::Result {
+    for (index, group) in groups.iter().enumerate() {
+        if index > 0 {
+            write!(f, ":")?;
+        }
+        write!(f, "{:x}", group)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpAddr::V4(a, b, c, d) => write!(f, "{}.{}.{}.{}", a, b, c, d),
+            IpAddr::V6(groups) => {
+                // IPv4-mapped addresses are shown with the dotted IPv4
+                // part, like `::ffff:10.0.0.1` (RFC 5952, section 5).
+                if let Some(ipv4) = self.to_ipv4_mapped() {
+                    return write!(f, "::ffff:{}", ipv4);
+                }
+                match longest_zero_run(groups) {
+                    Some((start, len)) => {
+                        write_groups(f, &groups[..start])?;
+                        write!(f, "::")?;
+                        write_groups(f, &groups[start + len..])
+                    }
+                    None => write_groups(f, groups),
+                }
+            }
+        }
+    }
+}
+
+// Conversions from and to the standard library types, so the addresses
+// can be used with `std::net` (for example, to open a connection).
+impl From<net::Ipv4Addr> for IpAddr {
+    fn from(addr: net::Ipv4Addr) -> IpAddr {
+        let [a, b, c, d] = addr.octets();
+        IpAddr::V4(a, b, c, d)
+    }
+}
+
+impl From<net::Ipv6Addr> for IpAddr {
+    fn from(addr: net::Ipv6Addr) -> IpAddr {
+        IpAddr::V6(addr.segments())
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(addr: net::IpAddr) -> IpAddr {
+        match addr {
+            net::IpAddr::V4(addr) => addr.into(),
+            net::IpAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl From<IpAddr> for net::IpAddr {
+    fn from(addr: IpAddr) -> net::IpAddr {
+        match addr {
+            IpAddr::V4(a, b, c, d) => net::IpAddr::V4(net::Ipv4Addr::new(a, b, c, d)),
+            IpAddr::V6(groups) => net::IpAddr::V6(net::Ipv6Addr::from(groups)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<IpAddr, AddrParseError> {
+        text.parse()
+    }
+
+    #[test]
+    fn parses_ipv4() {
+        assert_eq!(parse("10.0.0.1"), Ok(IpAddr::V4(10, 0, 0, 1)));
+        assert_eq!(parse(" 255.255.255.255 "), Ok(IpAddr::V4(255, 255, 255, 255)));
+        assert_eq!(parse(""), Err(AddrParseError::Empty));
+        assert_eq!(parse("10.0.1"), Err(AddrParseError::OctetCount(3)));
+        assert_eq!(parse("10.0.0.1.2"), Err(AddrParseError::OctetCount(5)));
+        assert_eq!(parse("10.0.0.256"), Err(AddrParseError::InvalidOctet("256".to_string())));
+        assert_eq!(parse("10..0.1"), Err(AddrParseError::InvalidOctet("".to_string())));
+        assert_eq!(parse("10.0.0.+1"), Err(AddrParseError::InvalidOctet("+1".to_string())));
+    }
+
+    #[test]
+    fn rejects_leading_zero_octets() {
+        assert_eq!(parse("10.0.0.0"), Ok(IpAddr::V4(10, 0, 0, 0)));
+        assert_eq!(parse("010.0.0.1"), Err(AddrParseError::InvalidOctet("010".to_string())));
+        assert_eq!(parse("10.00.0.1"), Err(AddrParseError::InvalidOctet("00".to_string())));
+        assert_eq!(parse("::ffff:10.0.0.01"), Err(AddrParseError::InvalidOctet("01".to_string())));
+    }
+
+    #[test]
+    fn parses_compressed_ipv6() {
+        assert_eq!(parse("::"), Ok(IpAddr::V6([0; 8])));
+        assert_eq!(parse("::1"), Ok(IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1])));
+        assert_eq!(parse("1::"), Ok(IpAddr::V6([1, 0, 0, 0, 0, 0, 0, 0])));
+        assert_eq!(parse("2001:DB8::8:800:200c:417a"), Ok(IpAddr::V6([0x2001, 0xdb8, 0, 0, 8, 0x800, 0x200c, 0x417a])));
+        assert_eq!(parse("1:2:3:4:5:6:7::"), Ok(IpAddr::V6([1, 2, 3, 4, 5, 6, 7, 0])));
+        assert_eq!(parse("1:2:3:4:5:6:7:8"), Ok(IpAddr::V6([1, 2, 3, 4, 5, 6, 7, 8])));
+    }
+
+    #[test]
+    fn rejects_invalid_ipv6() {
+        assert_eq!(parse(":::"), Err(AddrParseError::InvalidGroup("".to_string())));
+        assert_eq!(parse("1::2::3"), Err(AddrParseError::MultipleCompression));
+        assert_eq!(parse("1:2:3:4:5:6:7"), Err(AddrParseError::GroupCount(7)));
+        assert_eq!(parse("1:2:3:4::5:6:7:8"), Err(AddrParseError::GroupCount(8)));
+        assert_eq!(parse(":1:2:3:4:5:6:7"), Err(AddrParseError::InvalidGroup("".to_string())));
+        assert_eq!(parse("1:2:3:4:5:6:7:12345"), Err(AddrParseError::InvalidGroup("12345".to_string())));
+        assert_eq!(parse("::g"), Err(AddrParseError::InvalidGroup("g".to_string())));
+    }
+
+    #[test]
+    fn parses_embedded_ipv4() {
+        let mapped = parse("::ffff:192.168.1.2").unwrap();
+        assert_eq!(mapped, IpAddr::V6([0, 0, 0, 0, 0, 0xffff, 0xc0a8, 0x0102]));
+        assert_eq!(mapped.to_ipv4_mapped(), Some(IpAddr::V4(192, 168, 1, 2)));
+        assert_eq!(IpAddr::V4(192, 168, 1, 2).to_ipv6(), mapped);
+        assert_eq!(mapped.to_string(), "::ffff:192.168.1.2");
+        assert_eq!(parse("::ffff:10.0.0"), Err(AddrParseError::OctetCount(3)));
+        assert_eq!(parse("10.0.0.1::"), Err(AddrParseError::MisplacedIpv4));
+        assert_eq!(parse("::10.0.0.1:1"), Err(AddrParseError::MisplacedIpv4));
+    }
+
+    #[test]
+    fn formats_canonical_ipv6() {
+        for (text, canonical) in [
+            ("0:0:0:0:0:0:0:0", "::"),
+            ("0:0:0:0:0:0:0:1", "::1"),
+            ("1:0:0:0:0:0:0:0", "1::"),
+            ("2001:0db8:0000:0000:0000:0000:0000:0001", "2001:db8::1"),
+            // One zero group is not compressed.
+            ("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+            // The longest run, the first one of equal runs.
+            ("1:0:0:2:0:0:0:3", "1:0:0:2::3"),
+            ("1:0:0:2:3:0:0:4", "1::2:3:0:0:4"),
+        ] {
+            assert_eq!(parse(text).unwrap().to_string(), canonical);
+            // The same text as the standard library produces.
+            let std: net::IpAddr = text.parse().unwrap();
+            assert_eq!(std.to_string(), canonical);
+            assert_eq!(IpAddr::from(std), parse(text).unwrap());
+        }
+    }
+
+    #[test]
+    fn converts_to_std() {
+        let addr = parse("127.0.0.1").unwrap();
+        assert!(addr.is_ipv4() && addr.is_loopback());
+        assert_eq!(net::IpAddr::from(addr), net::IpAddr::V4(net::Ipv4Addr::LOCALHOST));
+        let addr = parse("::1").unwrap();
+        assert!(addr.is_ipv6() && addr.is_loopback());
+        assert_eq!(net::IpAddr::from(addr), net::IpAddr::V6(net::Ipv6Addr::LOCALHOST));
+    }
+}
@@ -0,0 +1,17 @@
+// The IP address, network, message and coin types are in the library part of
+// the crate, so they can be reused (the `src/main.rs` binary, the
+// `src/bin/subnet.rs` calculator and the `src/bin/messages.rs` REPL are
+// the users).
+pub mod change;
+pub mod cidr;
+pub mod event_log;
+pub mod ip;
+pub mod message;
+pub mod vending;
+
+pub use change::{Breakdown, ChangeMaker, Coin};
+pub use cidr::{aggregate, CidrError, Network};
+pub use event_log::{Event, EventLog, LogError, Snapshot, State};
+pub use ip::{AddrParseError, IpAddr};
+pub use message::{CommandError, DecodeError, Dispatcher, Message, MessageHandler};
+pub use vending::{MachineState, Product, Sale, VendError, VendingMachine};
@@ -1,7 +1,11 @@
+use enums::IpAddr as Address;
+use enums::{ChangeMaker, Coin as UsCoin, Message, VendingMachine};
+
 fn main() {
     enum_definition();
     option_enum();
     enum_match();
+    coin_change();
     enum_match_pattern_bind();
     option_match();
     default_match();
@@ -19,6 +23,8 @@ fn enum_definition() {
     let _six = IpAddrKind::V6;
 
     // Define Enum with attachable String value:
+    // (`allow(dead_code)`: the values are never read in these examples)
+    #[allow(dead_code)]
     enum IpAddr {
         V4(String),
         V6(String),
@@ -28,6 +34,7 @@ fn enum_definition() {
 
     // We can have different types attached to different
     // Enum values:
+    #[allow(dead_code)]
     enum IpAddr2 {
         V4(u8, u8, u8, u8),
         V6(String),
@@ -35,23 +42,18 @@ fn enum_definition() {
     let _home = IpAddr2::V4(127, 0, 0, 1);
     let _loopback = IpAddr2::V6(String::from("::1"));
 
-    // We can put any kind of data inside an enum variant:
-    // strings, numeric types, or structs, or even another enum.
-    #[derive(Debug)]
-    enum Message {
-        Quit,                       // no associated data
-        Move { x: i32, y: i32 },    // anonymous struct
-        Write(String),              // String
-        ChangeColor(i32, i32, i32), // three i32 values
-    }
-
-    // We can define methods for Enums:
-    impl Message {
-        fn call(&self) {
-            // method body would be defined here
-            println!("{:#?}", self);
+    // The enums above only store the data, see `src/ip.rs` for the
+    // `IpAddr` enum with parsing and formatting:
+    for text in &["127.0.0.1", "::1", "2001:0db8:0000:0000:0000:0000:0002:0001", "::ffff:10.0.0.1", "1.2.3.256", "1::2::3"] {
+        match text.parse::<Address>() {
+            Ok(addr) => println!("{} -> {} ({:?})", text, addr, addr),
+            Err(err) => println!("{} -> error: {}", text, err),
         }
     }
+
+    // We can put any kind of data inside an enum variant:
+    // strings, numeric types, or structs, or even another enum.
+    // See the `Message` enum and its methods in `src/message.rs`.
     let message = Message::Write(String::from("hello"));
     message.call();
     let message = Message::Move{x: 10, y: 100};
@@ -60,8 +62,15 @@ fn enum_definition() {
     message.call();
     let message = Message::Quit;
     message.call();
+
+    // Messages can be parsed from text commands and encoded as JSON or
+    // bytes, see also the `src/bin/messages.rs` REPL:
+    let message: Message = "move 10 100".parse().expect("valid command");
+    println!("{} -> {} -> {:?}", message, message.to_json(), message.encode());
 }
 
+// Clippy suggests to avoid the `unwrap` here, but it is what we demonstrate.
+#[allow(clippy::unnecessary_literal_unwrap)]
 fn option_enum() {
     // The `Option` enum from the standard library represents
     // value - or - null scenario.
@@ -69,6 +78,7 @@ fn option_enum() {
     // Some and None options; also Some and None can be used without
     // the `Option::` prefix.
     // The `Option` enum is defined approximately like this:
+    #[allow(dead_code)]
     enum MyOption<T> {
         Some(T),
         None,
@@ -144,6 +154,33 @@ fn enum_match() {
     println!("{}", print_value_in_cents(Coin::Quarter));
 }
 
+fn coin_change() {
+    // The `Coin` enum from `src/change.rs` is used to give change and in
+    // the vending machine (see `src/vending.rs`).
+    let us_coins = ChangeMaker::for_coins(&UsCoin::ALL);
+    println!("Change for 68 cents: {:?}", us_coins.make_change(68));
+    // Greedy would give 4 + 1 + 1 here, the optimal change is 3 + 3:
+    println!("Change for 6 with 1, 3, 4: {:?}", ChangeMaker::new(&[1, 3, 4]).make_change(6));
+
+    let mut machine = VendingMachine::new();
+    machine.add_product("chips", 65, 2);
+    machine.load_coins(UsCoin::Dime, 1);
+    println!("Exact change only: {}", machine.exact_change_only());
+    for _ in 0..3 {
+        machine.insert(UsCoin::Quarter);
+    }
+    // 10 cents of change, the machine has one dime:
+    println!("{:?}", machine.select("chips"));
+    for _ in 0..3 {
+        machine.insert(UsCoin::Quarter);
+    }
+    // No more dimes, so the sale is refused and the coins can be returned:
+    if let Err(err) = machine.select("chips") {
+        println!("Error: {}", err);
+    }
+    println!("Returned: {:?}", machine.cancel());
+}
+
 fn enum_match_pattern_bind() {
     // Patterns that bind to values.
     #[derive(Debug)] // So we can inspect the state
@@ -184,6 +221,8 @@ fn enum_match_pattern_bind() {
 
 fn option_match() {
     // The `match` operator is useful with Option<T> enum.
+    // (the same can be done with `x.map(|i| i + 1)`)
+    #[allow(clippy::manual_map)]
     fn plus_one(x: Option<i32>) -> Option<i32> {
         match x {
             None => None,
@@ -210,6 +249,9 @@ fn default_match() {
     }
 }
 
+// Clippy suggests to replace the first `match` with `if let`, which is
+// exactly what we do next.
+#[allow(clippy::single_match)]
 fn if_let_match() {
     // The if-let construct is useful when we only want to do something
     // for one specific match:
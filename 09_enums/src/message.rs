@@ -0,0 +1,456 @@
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+// We can put any kind of data inside an enum variant:
+// strings, numeric types, or structs, or even another enum.
+//
+// The messages are commands for a simple drawing program, they can be
+// sent as text (`move 10 100`), JSON (`{"Move":{"x":10,"y":100}}`) or in
+// the compact binary format (see `Message::write_to`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Message {
+    Quit,                       // no associated data
+    Move { x: i32, y: i32 },    // anonymous struct
+    Write(String),              // String
+    ChangeColor(i32, i32, i32), // three i32 values
+}
+
+// Tags of the variants in the binary format.
+const TAG_QUIT: u8 = 0;
+const TAG_MOVE: u8 = 1;
+const TAG_WRITE: u8 = 2;
+const TAG_CHANGE_COLOR: u8 = 3;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    UnknownTag(u8),
+    // The data ends in the middle of the message.
+    UnexpectedEof,
+    InvalidUtf8,
+    // `Message::decode` expects exactly one message, holds the number of
+    // extra bytes.
+    TrailingBytes(usize),
+    Json(serde_json::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownTag(tag) => write!(f, "unknown message tag {}", tag),
+            DecodeError::UnexpectedEof => write!(f, "message is truncated"),
+            DecodeError::InvalidUtf8 => write!(f, "text is not valid UTF-8"),
+            DecodeError::TrailingBytes(count) => write!(f, "{} extra bytes after the message", count),
+            DecodeError::Json(err) => write!(f, "invalid JSON message: {}", err),
+            DecodeError::Io(err) => write!(f, "failed to read message: {}", err),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(err: io::Error) -> DecodeError {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            DecodeError::UnexpectedEof
+        } else {
+            DecodeError::Io(err)
+        }
+    }
+}
+
+impl From<serde_json::Error> for DecodeError {
+    fn from(err: serde_json::Error) -> DecodeError {
+        DecodeError::Json(err)
+    }
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32, DecodeError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_be_bytes(bytes))
+}
+
+impl Message {
+    // We can define methods for Enums:
+    pub fn call(&self) {
+        // method body would be defined here
+        println!("{:#?}", self);
+    }
+
+    // Write the message in the binary format: one byte tag followed by
+    // the variant data, numbers are big-endian `i32` and the text is
+    // the `u32` length followed by UTF-8 bytes:
+    //
+    //     Quit                   00
+    //     Move{x: 10, y: 100}    01 0000000a 00000064
+    //     Write("hi")            02 00000002 6869
+    //     ChangeColor(1, 2, 3)   03 00000001 00000002 00000003
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Message::Quit => writer.write_all(&[TAG_QUIT]),
+            Message::Move { x, y } => {
+                writer.write_all(&[TAG_MOVE])?;
+                writer.write_all(&x.to_be_bytes())?;
+                writer.write_all(&y.to_be_bytes())
+            }
+            Message::Write(text) => {
+                writer.write_all(&[TAG_WRITE])?;
+                writer.write_all(&(text.len() as u32).to_be_bytes())?;
+                writer.write_all(text.as_bytes())
+            }
+            Message::ChangeColor(r, g, b) => {
+                writer.write_all(&[TAG_CHANGE_COLOR])?;
+                for value in &[r, g, b] {
+                    writer.write_all(&value.to_be_bytes())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Read the next message written by `write_to`, `None` if the
+    // input ends before the message.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Option<Message>, DecodeError> {
+        let mut tag = [0; 1];
+        if reader.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+        let message = match tag[0] {
+            TAG_QUIT => Message::Quit,
+            TAG_MOVE => Message::Move { x: read_i32(reader)?, y: read_i32(reader)? },
+            TAG_WRITE => {
+                let mut len = [0; 4];
+                reader.read_exact(&mut len)?;
+                // Read with `take` instead of allocating the buffer of
+                // the given length, so a broken length doesn't make us
+                // allocate gigabytes.
+                let len = u64::from(u32::from_be_bytes(len));
+                let mut bytes = Vec::new();
+                reader.take(len).read_to_end(&mut bytes)?;
+                if (bytes.len() as u64) < len {
+                    return Err(DecodeError::UnexpectedEof);
+                }
+                Message::Write(String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?)
+            }
+            TAG_CHANGE_COLOR => Message::ChangeColor(read_i32(reader)?, read_i32(reader)?, read_i32(reader)?),
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        };
+        Ok(Some(message))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes).expect("writing to Vec doesn't fail");
+        bytes
+    }
+
+    // Decode exactly one message.
+    pub fn decode(mut bytes: &[u8]) -> Result<Message, DecodeError> {
+        let message = Message::read_from(&mut bytes)?.ok_or(DecodeError::UnexpectedEof)?;
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes(bytes.len()));
+        }
+        Ok(message)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("message can always be serialized")
+    }
+
+    pub fn from_json(json: &str) -> Result<Message, DecodeError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+// Errors in the text commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Empty,
+    UnknownCommand(String),
+    // Wrong number of arguments, holds the usage of the command.
+    WrongArguments(&'static str),
+    InvalidNumber(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "empty command"),
+            CommandError::UnknownCommand(command) => {
+                write!(f, "unknown command '{}', expected quit, move, write or color", command)
+            }
+            CommandError::WrongArguments(usage) => write!(f, "usage: {}", usage),
+            CommandError::InvalidNumber(number) => write!(f, "'{}' is not a number", number),
+        }
+    }
+}
+
+impl error::Error for CommandError {}
+
+fn parse_numbers(args: &[&str], usage: &'static str, count: usize) -> Result<Vec<i32>, CommandError> {
+    if args.len() != count {
+        return Err(CommandError::WrongArguments(usage));
+    }
+    args.iter()
+        .map(|arg| arg.parse().map_err(|_| CommandError::InvalidNumber(arg.to_string())))
+        .collect()
+}
+
+// Text commands: `quit`, `move 10 100`, `write some text` and
+// `color 255 255 255`.
+impl FromStr for Message {
+    type Err = CommandError;
+
+    fn from_str(line: &str) -> Result<Message, CommandError> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        match command.to_lowercase().as_str() {
+            "" => Err(CommandError::Empty),
+            "quit" if args.is_empty() => Ok(Message::Quit),
+            "quit" => Err(CommandError::WrongArguments("quit")),
+            "move" => {
+                let numbers = parse_numbers(&args, "move <x> <y>", 2)?;
+                Ok(Message::Move { x: numbers[0], y: numbers[1] })
+            }
+            // The text is the rest of the line as is.
+            "write" => Ok(Message::Write(rest.trim_start().to_string())),
+            "color" => {
+                let numbers = parse_numbers(&args, "color <r> <g> <b>", 3)?;
+                Ok(Message::ChangeColor(numbers[0], numbers[1], numbers[2]))
+            }
+            _ => Err(CommandError::UnknownCommand(command.to_string())),
+        }
+    }
+}
+
+// Show the message as the text command, so it can be parsed back.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Quit => write!(f, "quit"),
+            Message::Move { x, y } => write!(f, "move {} {}", x, y),
+            Message::Write(text) => write!(f, "write {}", text),
+            Message::ChangeColor(r, g, b) => write!(f, "color {} {} {}", r, g, b),
+        }
+    }
+}
+
+// Handler for the messages, each variant is routed to its own method.
+// The methods do nothing by default, so the handler only implements the
+// messages it is interested in.
+pub trait MessageHandler {
+    fn quit(&mut self) {}
+
+    fn move_to(&mut self, _x: i32, _y: i32) {}
+
+    fn write(&mut self, _text: &str) {}
+
+    fn change_color(&mut self, _r: i32, _g: i32, _b: i32) {}
+
+    fn handle(&mut self, message: &Message) {
+        match message {
+            Message::Quit => self.quit(),
+            Message::Move { x, y } => self.move_to(*x, *y),
+            Message::Write(text) => self.write(text),
+            Message::ChangeColor(r, g, b) => self.change_color(*r, *g, *b),
+        }
+    }
+}
+
+// A mutable reference to a handler is a handler too, so we can register
+// `&mut handler` and still use the handler after dispatching.
+// All methods are forwarded, so the calls reach the handler's own
+// implementations and not the defaults.
+impl<H: MessageHandler + ?Sized> MessageHandler for &mut H {
+    fn quit(&mut self) {
+        (**self).quit()
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        (**self).move_to(x, y)
+    }
+
+    fn write(&mut self, text: &str) {
+        (**self).write(text)
+    }
+
+    fn change_color(&mut self, r: i32, g: i32, b: i32) {
+        (**self).change_color(r, g, b)
+    }
+
+    fn handle(&mut self, message: &Message) {
+        (**self).handle(message)
+    }
+}
+
+// Sends every message to all registered handlers, in the order they
+// were registered.
+#[derive(Default)]
+pub struct Dispatcher<'a> {
+    handlers: Vec<Box<dyn MessageHandler + 'a>>,
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new() -> Dispatcher<'a> {
+        Dispatcher::default()
+    }
+
+    pub fn register<H: MessageHandler + 'a>(&mut self, handler: H) {
+        self.handlers.push(Box::new(handler));
+    }
+
+    // Returns `false` after the `Quit` message, so the caller knows it
+    // should stop.
+    pub fn dispatch(&mut self, message: &Message) -> bool {
+        for handler in self.handlers.iter_mut() {
+            handler.handle(message);
+        }
+        *message != Message::Quit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::Quit,
+            Message::Move { x: 10, y: -100 },
+            Message::Write("hi".to_string()),
+            Message::Write(String::new()),
+            Message::Write("привет".to_string()),
+            Message::ChangeColor(1, 2, 3),
+        ]
+    }
+
+    #[test]
+    fn binary_format() {
+        assert_eq!(Message::Quit.encode(), [0]);
+        assert_eq!(Message::Move { x: 10, y: 100 }.encode(), [1, 0, 0, 0, 10, 0, 0, 0, 100]);
+        assert_eq!(Message::Write("hi".to_string()).encode(), [2, 0, 0, 0, 2, b'h', b'i']);
+        assert_eq!(Message::ChangeColor(1, 2, 3).encode(), [3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        for message in messages() {
+            assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+        }
+        // Several messages in one stream.
+        let mut bytes = Vec::new();
+        for message in messages() {
+            message.write_to(&mut bytes).unwrap();
+        }
+        let mut reader = bytes.as_slice();
+        let mut decoded = Vec::new();
+        while let Some(message) = Message::read_from(&mut reader).unwrap() {
+            decoded.push(message);
+        }
+        assert_eq!(decoded, messages());
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert!(matches!(Message::decode(&[]), Err(DecodeError::UnexpectedEof)));
+        assert!(matches!(Message::decode(&[9]), Err(DecodeError::UnknownTag(9))));
+        assert!(matches!(Message::decode(&[0, 0, 1]), Err(DecodeError::TrailingBytes(2))));
+        assert!(matches!(Message::decode(&[2, 0, 0, 0, 2, 0xff, 0xfe]), Err(DecodeError::InvalidUtf8)));
+        // A huge length doesn't allocate, the text is just truncated.
+        assert!(matches!(Message::decode(&[2, 0xff, 0xff, 0xff, 0xff, b'a']), Err(DecodeError::UnexpectedEof)));
+        // Every prefix of a message is truncated.
+        for message in messages() {
+            let bytes = message.encode();
+            for len in 1..bytes.len() {
+                assert!(matches!(Message::decode(&bytes[..len]), Err(DecodeError::UnexpectedEof)), "{:?}", &bytes[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        assert_eq!(Message::Move { x: 10, y: 100 }.to_json(), r#"{"Move":{"x":10,"y":100}}"#);
+        assert_eq!(Message::Quit.to_json(), r#""Quit""#);
+        for message in messages() {
+            assert_eq!(Message::from_json(&message.to_json()).unwrap(), message);
+        }
+        assert!(matches!(Message::from_json(r#"{"Move":{"x":10}}"#), Err(DecodeError::Json(_))));
+        assert!(matches!(Message::from_json(r#"{"Move":{"x":10,"y":100}"#), Err(DecodeError::Json(_))));
+        assert!(matches!(Message::from_json(r#""Jump""#), Err(DecodeError::Json(_))));
+    }
+
+    #[test]
+    fn text_commands() {
+        for message in messages().into_iter().filter(|message| *message != Message::Write(String::new())) {
+            assert_eq!(message.to_string().parse::<Message>(), Ok(message));
+        }
+        assert_eq!("  MOVE 1   2 ".parse(), Ok(Message::Move { x: 1, y: 2 }));
+        assert_eq!("write  two  spaces".parse(), Ok(Message::Write("two  spaces".to_string())));
+        assert_eq!("".parse::<Message>(), Err(CommandError::Empty));
+        assert_eq!("jump".parse::<Message>(), Err(CommandError::UnknownCommand("jump".to_string())));
+        assert_eq!("quit now".parse::<Message>(), Err(CommandError::WrongArguments("quit")));
+        assert_eq!("move 1".parse::<Message>(), Err(CommandError::WrongArguments("move <x> <y>")));
+        assert_eq!("color 1 2 x".parse::<Message>(), Err(CommandError::InvalidNumber("x".to_string())));
+    }
+
+    // Records the calls of the variant methods.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl MessageHandler for Recorder {
+        fn quit(&mut self) {
+            self.calls.push("quit".to_string());
+        }
+
+        fn move_to(&mut self, x: i32, y: i32) {
+            self.calls.push(format!("move_to {} {}", x, y));
+        }
+
+        fn write(&mut self, text: &str) {
+            self.calls.push(format!("write {}", text));
+        }
+
+        fn change_color(&mut self, r: i32, g: i32, b: i32) {
+            self.calls.push(format!("change_color {} {} {}", r, g, b));
+        }
+    }
+
+    // Generic, so for `H = &mut Recorder` the `&mut H` implementation is
+    // called and not the `Recorder` methods directly.
+    fn call_all<H: MessageHandler>(mut handler: H) {
+        handler.quit();
+        handler.move_to(1, 2);
+        handler.write("hi");
+        handler.change_color(1, 2, 3);
+    }
+
+    #[test]
+    fn reference_forwards_all_methods() {
+        let mut recorder = Recorder::default();
+        call_all(&mut recorder);
+        assert_eq!(recorder.calls, ["quit", "move_to 1 2", "write hi", "change_color 1 2 3"]);
+    }
+
+    #[test]
+    fn dispatcher_calls_handlers_in_order() {
+        let mut first = Recorder::default();
+        let mut second = Recorder::default();
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register(&mut first);
+        dispatcher.register(&mut second);
+        assert!(dispatcher.dispatch(&Message::Move { x: 1, y: 2 }));
+        assert!(dispatcher.dispatch(&Message::Write("hi".to_string())));
+        assert!(!dispatcher.dispatch(&Message::Quit));
+        drop(dispatcher);
+        assert_eq!(first.calls, ["move_to 1 2", "write hi", "quit"]);
+        assert_eq!(second.calls, first.calls);
+    }
+}
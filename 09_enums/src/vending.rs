@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use crate::change::{Breakdown, ChangeMaker, Coin};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Product {
+    pub name: String,
+    pub price: u32,
+    pub stock: u32,
+}
+
+// The machine is either waiting for coins or collecting them: the
+// inserted coins stay separate from the coin box until the sale, so
+// `cancel` returns exactly the coins the customer inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineState {
+    Idle,
+    Collecting { credit: u32, inserted: Vec<Coin> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VendError {
+    UnknownProduct(String),
+    SoldOut(String),
+    InsufficientCredit { price: u32, credit: u32 },
+    // The machine doesn't have the coins for the change, the credit is
+    // kept, so the customer can insert the exact amount or cancel.
+    CannotMakeChange { change: u32 },
+}
+
+impl fmt::Display for VendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VendError::UnknownProduct(name) => write!(f, "unknown product '{}'", name),
+            VendError::SoldOut(name) => write!(f, "{} is sold out", name),
+            VendError::InsufficientCredit { price, credit } => {
+                write!(f, "price is {} cents, inserted {} cents", price, credit)
+            }
+            VendError::CannotMakeChange { change } => {
+                write!(f, "can not give {} cents change, please use exact change", change)
+            }
+        }
+    }
+}
+
+impl error::Error for VendError {}
+
+// The result of a successful sale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sale {
+    pub product: String,
+    pub change: Vec<Coin>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VendingMachine {
+    state: MachineState,
+    products: BTreeMap<String, Product>,
+    // The coins in the machine: coin -> count.
+    coins: BTreeMap<Coin, u32>,
+    change_maker: ChangeMaker,
+}
+
+impl Default for VendingMachine {
+    fn default() -> VendingMachine {
+        VendingMachine::new()
+    }
+}
+
+fn to_breakdown(coins: &BTreeMap<Coin, u32>) -> Breakdown {
+    coins.iter().map(|(coin, &count)| (coin.value_in_cents(), count)).collect()
+}
+
+fn to_coins(breakdown: &Breakdown) -> Vec<Coin> {
+    let mut coins = Vec::new();
+    // Largest coins first.
+    for (&cents, &count) in breakdown.iter().rev() {
+        let coin = Coin::from_cents(cents).expect("breakdown is made of coins");
+        coins.extend(std::iter::repeat_n(coin, count as usize));
+    }
+    coins
+}
+
+impl VendingMachine {
+    pub fn new() -> VendingMachine {
+        VendingMachine {
+            state: MachineState::Idle,
+            products: BTreeMap::new(),
+            coins: BTreeMap::new(),
+            change_maker: ChangeMaker::for_coins(&Coin::ALL),
+        }
+    }
+
+    pub fn add_product(&mut self, name: &str, price: u32, stock: u32) {
+        let product = self.products.entry(name.to_string())
+            .or_insert(Product { name: name.to_string(), price, stock: 0 });
+        product.price = price;
+        product.stock += stock;
+    }
+
+    // Put coins into the coin box (for the change).
+    pub fn load_coins(&mut self, coin: Coin, count: u32) {
+        *self.coins.entry(coin).or_insert(0) += count;
+    }
+
+    pub fn state(&self) -> &MachineState {
+        &self.state
+    }
+
+    pub fn credit(&self) -> u32 {
+        match &self.state {
+            MachineState::Idle => 0,
+            MachineState::Collecting { credit, .. } => *credit,
+        }
+    }
+
+    pub fn products(&self) -> impl Iterator<Item = &Product> {
+        self.products.values()
+    }
+
+    pub fn coins(&self) -> &BTreeMap<Coin, u32> {
+        &self.coins
+    }
+
+    // Returns the credit after inserting the coin.
+    pub fn insert(&mut self, coin: Coin) -> u32 {
+        let state = std::mem::replace(&mut self.state, MachineState::Idle);
+        self.state = match state {
+            MachineState::Idle => MachineState::Collecting { credit: coin.value_in_cents(), inserted: vec![coin] },
+            MachineState::Collecting { credit, mut inserted } => {
+                inserted.push(coin);
+                MachineState::Collecting { credit: credit + coin.value_in_cents(), inserted }
+            }
+        };
+        self.credit()
+    }
+
+    // Return the inserted coins.
+    pub fn cancel(&mut self) -> Vec<Coin> {
+        match std::mem::replace(&mut self.state, MachineState::Idle) {
+            MachineState::Idle => Vec::new(),
+            MachineState::Collecting { inserted, .. } => inserted,
+        }
+    }
+
+    // Sell the product, the change is paid from all coins in the
+    // machine, including the inserted ones.
+    pub fn select(&mut self, name: &str) -> Result<Sale, VendError> {
+        let product = self.products.get(name).ok_or_else(|| VendError::UnknownProduct(name.to_string()))?;
+        if product.stock == 0 {
+            return Err(VendError::SoldOut(name.to_string()));
+        }
+        let credit = self.credit();
+        if credit < product.price {
+            return Err(VendError::InsufficientCredit { price: product.price, credit });
+        }
+
+        let mut coins = self.coins.clone();
+        if let MachineState::Collecting { inserted, .. } = &self.state {
+            for &coin in inserted {
+                *coins.entry(coin).or_insert(0) += 1;
+            }
+        }
+        let change = credit - product.price;
+        let breakdown = self.change_maker.make_change_limited(change, &to_breakdown(&coins))
+            .ok_or(VendError::CannotMakeChange { change })?;
+
+        // Everything is checked, now we can change the machine state.
+        let change = to_coins(&breakdown);
+        for coin in &change {
+            *coins.get_mut(coin).expect("change is paid from the machine coins") -= 1;
+        }
+        self.coins = coins;
+        self.products.get_mut(name).expect("product exists").stock -= 1;
+        self.state = MachineState::Idle;
+        Ok(Sale { product: name.to_string(), change })
+    }
+
+    // The machine can't give change for some amount below the largest
+    // coin, so it should show the "exact change only" sign.
+    pub fn exact_change_only(&self) -> bool {
+        let available = to_breakdown(&self.coins);
+        let largest = Coin::ALL.iter().map(|coin| coin.value_in_cents()).max().unwrap_or(0);
+        (1..largest).any(|amount| self.change_maker.make_change_limited(amount, &available).is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> VendingMachine {
+        let mut machine = VendingMachine::new();
+        machine.add_product("cola", 65, 2);
+        machine.add_product("gum", 25, 0);
+        machine
+    }
+
+    #[test]
+    fn sells_and_gives_change() {
+        let mut machine = machine();
+        machine.load_coins(Coin::Dime, 5);
+        for _ in 0..3 {
+            machine.insert(Coin::Quarter);
+        }
+        assert_eq!(machine.credit(), 75);
+        let sale = machine.select("cola").unwrap();
+        assert_eq!(sale, Sale { product: "cola".to_string(), change: vec![Coin::Dime] });
+        assert_eq!(machine.state(), &MachineState::Idle);
+        assert_eq!(machine.coins()[&Coin::Quarter], 3);
+        assert_eq!(machine.coins()[&Coin::Dime], 4);
+        assert_eq!(machine.products().find(|product| product.name == "cola").unwrap().stock, 1);
+    }
+
+    #[test]
+    fn change_can_use_inserted_coins() {
+        // The coin box is empty, the change is one of the inserted dimes.
+        let mut machine = machine();
+        machine.add_product("mints", 20, 1);
+        for _ in 0..3 {
+            machine.insert(Coin::Dime);
+        }
+        assert_eq!(machine.select("mints").unwrap().change, vec![Coin::Dime]);
+        assert_eq!(machine.coins(), &BTreeMap::from([(Coin::Dime, 2)]));
+    }
+
+    #[test]
+    fn cannot_make_change_keeps_credit() {
+        let mut machine = machine();
+        machine.insert(Coin::Quarter);
+        machine.insert(Coin::Quarter);
+        machine.insert(Coin::Quarter);
+        assert_eq!(machine.select("cola"), Err(VendError::CannotMakeChange { change: 10 }));
+        // Nothing changed: the credit and the stock are kept.
+        assert_eq!(machine.credit(), 75);
+        assert!(machine.coins().is_empty());
+        assert_eq!(machine.products().find(|product| product.name == "cola").unwrap().stock, 2);
+        // The customer can add coins for the change or cancel.
+        machine.load_coins(Coin::Dime, 1);
+        assert_eq!(machine.select("cola").unwrap().change, vec![Coin::Dime]);
+
+        let mut machine = self::machine();
+        machine.insert(Coin::Quarter);
+        machine.insert(Coin::Quarter);
+        machine.insert(Coin::Quarter);
+        assert!(machine.select("cola").is_err());
+        assert_eq!(machine.cancel(), vec![Coin::Quarter; 3]);
+        assert_eq!(machine.credit(), 0);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut machine = machine();
+        assert_eq!(machine.select("tea"), Err(VendError::UnknownProduct("tea".to_string())));
+        assert_eq!(machine.select("gum"), Err(VendError::SoldOut("gum".to_string())));
+        machine.insert(Coin::Quarter);
+        assert_eq!(machine.select("cola"), Err(VendError::InsufficientCredit { price: 65, credit: 25 }));
+        assert_eq!(machine.credit(), 25);
+        assert_eq!(machine.cancel(), vec![Coin::Quarter]);
+        assert_eq!(machine.cancel(), Vec::new());
+    }
+
+    #[test]
+    fn exact_change_only() {
+        let mut machine = machine();
+        assert!(machine.exact_change_only());
+        // Dimes and nickels, but no pennies: 1 to 4 cents are missing.
+        machine.load_coins(Coin::Dime, 2);
+        machine.load_coins(Coin::Nickel, 1);
+        assert!(machine.exact_change_only());
+        // With 4 pennies every amount below a quarter can be paid.
+        machine.load_coins(Coin::Penny, 4);
+        assert!(!machine.exact_change_only());
+
+        // Only pennies: up to 24 cents.
+        let mut machine = self::machine();
+        machine.load_coins(Coin::Penny, 23);
+        assert!(machine.exact_change_only());
+        machine.load_coins(Coin::Penny, 1);
+        assert!(!machine.exact_change_only());
+    }
+}
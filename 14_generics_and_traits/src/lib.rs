@@ -0,0 +1,5 @@
+// The generic `Point` type is in the library part of the crate, so it
+// can be reused (the `src/main.rs` binary is one of the users).
+pub mod point;
+
+pub use point::{Point, Scalar};
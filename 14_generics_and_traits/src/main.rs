@@ -1,5 +1,8 @@
 use std::fmt::Display;
 use std::cmp::PartialOrd;
+use std::f64::consts::PI;
+
+use generics::Point;
 
 // A generic function with function parameter
 fn run_demo<F>(f: F) where F: Fn() {
@@ -19,23 +22,9 @@ fn main() {
     run_demo(demo_summary_trait);
 }
 
-// Generic struct, both x and y should have the same type.
-struct Point<T> {
-    x: T,
-    y: T,
-}
-// Generic method.
-impl<T> Point<T> {
-    fn x(&self) -> &T {
-        &self.x
-    }
-}
-// f32-only method.
-impl Point<f32> {
-    fn distance_from_origin(&self) -> f32 {
-        (self.x.powi(2) + self.y.powi(2)).sqrt()
-    }
-}
+// Generic struct `Point<T>` with generic and f32-only methods is defined
+// in `src/point.rs`, it also shows how to overload operators like `+`
+// for a generic type.
 
 // A struct with different types for x and y.
 struct Point2<T, U> {
@@ -88,6 +77,15 @@ fn demo_point_generics() {
     let pt2 = Point2 {x: "Hello", y: 'c'};
     let pt3 = pt1.mixup(pt2);
     println!("pt3.x = {}, pt3.y = {}", pt3.x, pt3.y);
+
+    // Vector operations work both for integer and float points:
+    let a = Point::new(3, 4);
+    let b = Point::new(1, 2);
+    println!("a + b = {}, a - b = {}, -a = {}, a * 2 = {}", a + b, a - b, -a, a * 2);
+    println!("dot = {}, cross = {}, |a| = {}", a.dot(b), a.cross(b), a.length());
+    println!("distance = {:.3}, rotated = {}", a.distance_to(b), a.rotate(PI / 2.0));
+    let c = Point::new(1.5, -2.0);
+    println!("c * 2 = {}, normalized = {:?}", c * 2.0, c.normalize());
 }
 
 
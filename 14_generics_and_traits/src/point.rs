@@ -0,0 +1,216 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Numeric type that can be used for `Point` coordinates.
+//
+// The `Point` methods only need basic arithmetic (which comes from the
+// `Add`, `Sub` and `Mul` trait bounds) and a conversion to and from `f64`
+// for the operations that need square roots and trigonometry.
+pub trait Scalar: Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    fn zero() -> Self;
+    // Convert to f64 (can lose precision for large 64-bit integers).
+    fn to_f64(self) -> f64;
+    // Convert from f64, integers are rounded to the nearest value.
+    fn from_f64(value: f64) -> Self;
+}
+
+// The implementation is the same for all integer types, so we generate
+// it with a macro.
+macro_rules! impl_scalar_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                fn zero() -> $t {
+                    0
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+                fn from_f64(value: f64) -> $t {
+                    value.round() as $t
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_scalar_for_float {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                fn zero() -> $t {
+                    0.0
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+                fn from_f64(value: f64) -> $t {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+// Only signed types: vector operations like `a - b`, `cross` or
+// `rotate` give negative values, which would overflow (panic) for
+// unsigned types.
+impl_scalar_for_int!(i32, i64);
+impl_scalar_for_float!(f32, f64);
+
+// Generic struct, both x and y should have the same type.
+//
+// The point is also a 2D vector (from the origin to the point), so it
+// supports vector operations: `a + b`, `a - b`, `-a`, `a * 2`, the dot
+// and cross products, length, rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+// Generic methods, available for any `T`.
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+
+    pub fn x(&self) -> &T {
+        &self.x
+    }
+}
+
+// f32-only method.
+impl Point<f32> {
+    pub fn distance_from_origin(&self) -> f32 {
+        (self.x.powi(2) + self.y.powi(2)).sqrt()
+    }
+}
+
+// Methods for numeric coordinates, available for the types implementing
+// the `Scalar` trait.
+impl<T: Scalar> Point<T> {
+    pub fn dot(self, other: Point<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    // The z coordinate of the 3D cross product: positive if `other` is
+    // counterclockwise from `self`, negative if clockwise and zero if
+    // the vectors are parallel.
+    pub fn cross(self, other: Point<T>) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    // Exact for integers, unlike `length`.
+    pub fn length_squared(self) -> T {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().to_f64().sqrt()
+    }
+
+    pub fn distance_to(self, other: Point<T>) -> f64 {
+        (other - self).length()
+    }
+
+    // The vector with the same direction and length 1, `None` for the
+    // zero vector which has no direction.
+    // The result is always `Point<f64>`: an integer unit vector would be
+    // rounded to one of the axes.
+    pub fn normalize(self) -> Option<Point<f64>> {
+        let length = self.length();
+        if length == 0.0 {
+            return None;
+        }
+        Some(Point::new(self.x.to_f64() / length, self.y.to_f64() / length))
+    }
+
+    // Rotate counterclockwise around the origin by `angle` radians.
+    // Integer coordinates are rounded, so rotations by multiples of
+    // 90 degrees are exact and others are approximate.
+    pub fn rotate(self, angle: f64) -> Point<T> {
+        let (sin, cos) = angle.sin_cos();
+        let (x, y) = (self.x.to_f64(), self.y.to_f64());
+        Point::new(T::from_f64(x * cos - y * sin), T::from_f64(x * sin + y * cos))
+    }
+}
+
+// Operator overloading: `Point + Point`, `Point - Point`, `-Point` and
+// `Point * T` are implemented with the traits from `std::ops`.
+// The trait bounds make them available only when `T` itself supports
+// the operation, for example, `-Point<u32>` doesn't compile.
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point<T> {
+    type Output = Point<T>;
+
+    fn neg(self) -> Point<T> {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point<T> {
+    type Output = Point<T>;
+
+    fn mul(self, scalar: T) -> Point<T> {
+        Point::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn operators() {
+        let a = Point::new(3, 4);
+        let b = Point::new(1, 2);
+        assert_eq!(a + b, Point::new(4, 6));
+        assert_eq!(a - b, Point::new(2, 2));
+        assert_eq!(-a, Point::new(-3, -4));
+        assert_eq!(a * 2, Point::new(6, 8));
+    }
+
+    #[test]
+    fn products_and_lengths() {
+        let a = Point::new(3, 4);
+        let b = Point::new(1, 2);
+        assert_eq!(a.dot(b), 11);
+        assert_eq!(a.cross(b), 2);
+        assert_eq!(b.cross(a), -2);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(Point::new(0, 1).distance_to(Point::new(1, 0)), 2f64.sqrt());
+        assert_eq!(Point::new(0.0, -2.0).normalize(), Some(Point::new(0.0, -1.0)));
+        assert_eq!(Point::new(0, 0).normalize(), None);
+    }
+
+    #[test]
+    fn rotation() {
+        assert_eq!(Point::new(3, 4).rotate(PI / 2.0), Point::new(-4, 3));
+        assert_eq!(Point::new(1, 0).rotate(PI), Point::new(-1, 0));
+        let rotated = Point::new(1.0_f64, 0.0).rotate(PI / 4.0);
+        assert!((rotated.x - rotated.y).abs() < 1e-12);
+    }
+}